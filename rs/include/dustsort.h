@@ -0,0 +1,26 @@
+/* C interface to dustsort, built with the `c-api` feature. */
+
+#ifndef DUSTSORT_H
+#define DUSTSORT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Sort the `len` elements at `ptr` in place. Null `ptr` or zero `len` is a no-op. */
+void dustsort_i32(int32_t *ptr, size_t len);
+void dustsort_i64(int64_t *ptr, size_t len);
+void dustsort_u32(uint32_t *ptr, size_t len);
+void dustsort_u64(uint64_t *ptr, size_t len);
+
+/* Sort by total order: -NaN < -inf < ... < -0.0 < 0.0 < ... < inf < NaN. */
+void dustsort_f64(double *ptr, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -2,8 +2,8 @@ use core::ptr;
 
 use crate::{
     buffer::Buffer,
-    dust::RATIO_BIN_MERGE,
     merge::{exponential_merge_left, merge_lazy, merge_left, merge_right},
+    options::SortOptions,
     util::{block_swap_length, conditional, insert_left, search_left, search_right, Less},
 };
 
@@ -112,6 +112,7 @@ unsafe fn local_merge<T, F: Less<T>>(
     n1: usize,
     n2: usize,
     invert: bool,
+    opts: &SortOptions,
     less: &mut F,
 ) -> usize {
     if invert {
@@ -120,9 +121,15 @@ unsafe fn local_merge<T, F: Less<T>>(
         }
 
         let rad = block_swap_length(s, n1, s.add(n1), n2 - 1, &mut |x, y| !less(y, x));
+
+        // An empty merge means the comparator is inconsistent
+        if rad == 0 && opts.lenient {
+            return local_merge_lazy(s, n1, n2, invert, less);
+        }
+
         buf.begin_merge(s.add(n1 - rad), rad);
 
-        if rad > (n1 - rad) / RATIO_BIN_MERGE {
+        if rad > (n1 - rad) / opts.ratio_bin_merge {
             merge_left(s.add(n1), rad, s, n1 - rad, s, less);
         } else {
             exponential_merge_left(s, n1 - rad, s.add(n1), rad, s, &mut |x, y| !less(y, x));
@@ -135,9 +142,15 @@ unsafe fn local_merge<T, F: Less<T>>(
         }
 
         let rad = block_swap_length(s, n1, s.add(n1), n2 - 1, less);
+
+        // An empty merge means the comparator is inconsistent
+        if rad == 0 && opts.lenient {
+            return local_merge_lazy(s, n1, n2, invert, less);
+        }
+
         buf.begin_merge(s.add(n1 - rad), rad);
 
-        if rad > (n1 - rad) / RATIO_BIN_MERGE {
+        if rad > (n1 - rad) / opts.ratio_bin_merge {
             merge_left(s, n1 - rad, s.add(n1), rad, s, less);
         } else {
             exponential_merge_left(s, n1 - rad, s.add(n1), rad, s, less);
@@ -176,6 +189,7 @@ unsafe fn local_merge_lazy<T, F: Less<T>>(
 /// and the block merge has access to an internal merge buffer iff `!in_place`.
 ///
 /// Return the starting position of the suffix which needs to be merged with the remainder.
+#[allow(clippy::too_many_arguments)]
 pub unsafe fn block_merge<T, F: Less<T>>(
     buf: &mut Buffer<T>,
     s: *mut T,
@@ -183,6 +197,7 @@ pub unsafe fn block_merge<T, F: Less<T>>(
     n2: usize,
     block_len: usize,
     in_place: bool,
+    opts: &SortOptions,
     less: &mut F,
 ) -> usize {
     // Sort blocks
@@ -211,7 +226,16 @@ pub unsafe fn block_merge<T, F: Less<T>>(
             }
 
             let pos = i * block_len;
-            frag = pos + local_merge(buf, s.add(frag), pos - frag, block_len, block_was_b, less);
+            frag = pos
+                + local_merge(
+                    buf,
+                    s.add(frag),
+                    pos - frag,
+                    block_len,
+                    block_was_b,
+                    opts,
+                    less,
+                );
             block_was_b ^= true;
         }
     }
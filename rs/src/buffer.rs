@@ -20,6 +20,9 @@ pub struct Buffer<T> {
 impl<T> Buffer<T> {
     // Insert the element pointed to by `item` into this buffer at index `index`, first rotating the
     // buffer to the right of it.
+    //
+    // No comparisons are made here, so a panicking comparator can only interrupt key collection
+    // between insertions, while the region is a valid permutation.
     unsafe fn insert(&mut self, item: *mut T, index: usize) {
         rotate(item.add(1), ptr_sub(self.start, item) - 1, self.len);
         self.start = item;
@@ -56,7 +59,8 @@ impl<T> Buffer<T> {
         }
     }
 
-    /// Begin a merge operation by swapping `cnt` buffer elements into position at `dst`.
+    /// Begin a merge operation by swapping `cnt` buffer elements into position at `dst`, which must
+    /// not overlap the buffer.
     pub unsafe fn begin_merge(&mut self, dst: *mut T, cnt: usize) {
         // Detect ord violations by enforcing non-zero merges
         if cnt == 0 {
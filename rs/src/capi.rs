@@ -0,0 +1,63 @@
+//! C entry points for sorting arrays of primitives in place.
+
+use core::slice;
+
+// Sort the `len` elements at `ptr` with `sort`, unless there are none.
+#[inline(always)]
+unsafe fn sort_raw<T>(ptr: *mut T, len: usize, sort: impl FnOnce(&mut [T]) -> bool) {
+    if !ptr.is_null() && len > 0 {
+        sort(slice::from_raw_parts_mut(ptr, len));
+    }
+}
+
+/// Sort the `len` elements at `ptr` stably.
+///
+/// # Safety
+///
+/// Unless `ptr` is null or `len == 0`, `ptr` must be valid for reads and writes of `len` elements
+/// that are not accessed through any other pointer for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn dustsort_i32(ptr: *mut i32, len: usize) {
+    sort_raw(ptr, len, crate::sort_i32);
+}
+
+/// Sort the `len` elements at `ptr` stably.
+///
+/// # Safety
+///
+/// See [`dustsort_i32`].
+#[no_mangle]
+pub unsafe extern "C" fn dustsort_i64(ptr: *mut i64, len: usize) {
+    sort_raw(ptr, len, crate::sort);
+}
+
+/// Sort the `len` elements at `ptr` stably.
+///
+/// # Safety
+///
+/// See [`dustsort_i32`].
+#[no_mangle]
+pub unsafe extern "C" fn dustsort_u32(ptr: *mut u32, len: usize) {
+    sort_raw(ptr, len, crate::sort_u32);
+}
+
+/// Sort the `len` elements at `ptr` stably.
+///
+/// # Safety
+///
+/// See [`dustsort_i32`].
+#[no_mangle]
+pub unsafe extern "C" fn dustsort_u64(ptr: *mut u64, len: usize) {
+    sort_raw(ptr, len, crate::sort);
+}
+
+/// Sort the `len` elements at `ptr` by the total order of [`f64::total_cmp`], like
+/// [`sort_floats_f64`](crate::sort_floats_f64).
+///
+/// # Safety
+///
+/// See [`dustsort_i32`].
+#[no_mangle]
+pub unsafe extern "C" fn dustsort_f64(ptr: *mut f64, len: usize) {
+    sort_raw(ptr, len, crate::sort_floats_f64);
+}
@@ -1,9 +1,16 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::mem::{align_of, size_of, MaybeUninit};
+
 use crate::{
     blocks::block_merge,
     buffer::Buffer,
-    merge::{merge, merge_in_place},
-    scan::{build_runs, next_non_desc_run, next_sorted_run},
-    util::{ptr_sub, Hole, Less},
+    merge::{merge, merge_in_place, merge_scratch},
+    observe::{ignore, Event},
+    options::SortOptions,
+    scan::{build_runs, extend_sorted_run, next_non_desc_run, sampled_non_desc},
+    small_sort::SmallSort,
+    util::{count_moves, insert_left, ptr_sub, search_right, Hole, Less},
 };
 
 /// Create runs of this size at the small-sort level.
@@ -20,31 +27,60 @@ pub const RATIO_BIN_MERGE: usize = 8;
 // Immediately switch to insertion sort if the array is smaller than this.
 const MIN_SCAN: usize = 8;
 
-// Avoid the overhead of block merge sort on arrays smaller than this.
-const MIN_MERGE_SORT: usize = 64;
+/// Avoid the overhead of block merge sort on arrays smaller than this.
+pub const MIN_MERGE_SORT: usize = 64;
 
 // For arrays smaller than this, use naive key collection. Although extremely rare, linear key
 // collect might make `O(n)` writes and more than `4 * n` comparisons, which isn't much better than
 // pure binary searches.
 const MIN_OPT_FIND_KEYS: usize = 4096;
 
-// Use a special strategy on arrays with less than this many comparatively unequal elements.
-const MIN_DISTINCT: usize = 12;
+/// Use a special strategy on arrays with less than this many comparatively unequal elements.
+pub const MIN_DISTINCT: usize = 12;
 
 // Use a special strategy on arrays which end in no more than this many unsorted blocks. This is
 // still applicable with arbitrarily many further elements, as long as those elements have no more
 // than `MIN_DISTINCT` comparatively unequal elements.
 const MAX_APPEND_BLOCKS: usize = 3;
 
-// Return the desired block length to sort `n` elements.
+// Insert unsorted tails up to this length into the sorted head with binary insertion. Longer
+// tails are cheaper to sort and merge than to insert one at a time.
+const MAX_INSERT_TAIL: usize = 4;
+
+// Sample this many evenly spaced elements to detect arrays with few inversions.
+const LOW_INVERSION_SAMPLES: usize = 16;
+
+// On arrays with few inversions, use insertion sort as long as elements are shifted by no more than
+// this many positions on average. This bounds the work wasted on a false positive by `O(n)`.
+const MAX_AVG_SHIFT: usize = 2;
+
+// Merge through a buffer on the stack if half the array fits in this many bytes. This is small
+// enough to be safe on any thread that can sort at all.
+const STACK_BUDGET: usize = 4096;
+
+// With an allocator, sort elements up to this many bytes large by merging back and forth between
+// the array and a buffer of the same length. Moves are cheap enough for this to beat moving the
+// shorter run out before every merge.
+#[cfg(feature = "alloc")]
+const MAX_PING_PONG_SIZE: usize = 8;
+
+// Uninitialized stack memory for `STACK_BUDGET` bytes, aligned for all but overaligned types.
+#[repr(align(16))]
+struct StackScratch([MaybeUninit<u8>; STACK_BUDGET]);
+
+// Return the desired block length to sort `n` elements. For `n > 0`, this is a power of two close
+// to `sqrt(n)` and at most `2^(usize::BITS / 2 + 1)`, so the shifts can't overflow.
 fn array_block_length(n: usize) -> usize {
-    let k = 1 << ((n.ilog2() + 1) / 2);
+    let k = 1 << n.ilog2().div_ceil(2);
     k << (k < n / k) as usize
 }
 
-// Return the desired block length for a buffer of size `buf_len`.
+// Return the desired block length for a buffer of size `buf_len`. For `buf_len > 0`, this is at
+// most `2 * ceil(buf_len / 3) <= buf_len + 1`, so the shift can't overflow and splitting off a
+// merge buffer of length `block_len - 1` never leaves a negative number of keys.
 fn buffer_block_length(buf_len: usize) -> usize {
-    2 << ((buf_len + 2) / 3).ilog2()
+    debug_assert!(buf_len > 0);
+    2 << buf_len.div_ceil(3).ilog2()
 }
 
 /// Sort `s..s + n` with insertion sort, assuming the first `i` elements are sorted.
@@ -65,62 +101,184 @@ pub unsafe fn insert_sort<T, F: Less<T>>(s: *mut T, i: usize, n: usize, less: &m
             hole.pos.write(hole.pos.sub(1).read());
             hole.pos = hole.pos.sub(odd as usize);
         }
+
+        count_moves(ptr_sub(s.add(i), hole.pos) + 1);
     }
 }
 
-// Sort `s..n` with a rotation-based merge sort, assuming the first `head` elements were already
-// sorted before runs of size `run` were created.
-unsafe fn merge_sort_in_place<T, F: Less<T>>(
+// Sort `s..s + n` with binary insertion, assuming the first `i` elements are sorted. This takes
+// `O(log n)` comparisons per element but `O(n)` moves.
+unsafe fn insert_sort_binary<T, F: Less<T>>(s: *mut T, i: usize, n: usize, less: &mut F) {
+    for i in i..n {
+        let cur = s.add(i);
+        insert_left(cur, i - search_right(s, i, cur, less));
+    }
+}
+
+// Sort `s..s + n` with insertion sort, assuming the first `i` elements are sorted, until at least
+// `budget` elements have been shifted in total. This takes `O(n + inversions)` time.
+//
+// Return the length of the sorted prefix.
+unsafe fn insert_sort_bounded<T, F: Less<T>>(
     s: *mut T,
-    head: usize,
+    mut i: usize,
     n: usize,
-    mut run: usize,
+    mut budget: usize,
     less: &mut F,
+) -> usize {
+    while i < n && budget > 0 {
+        let tmp = core::mem::ManuallyDrop::new(s.add(i).read());
+        let mut hole = Hole::new(s.add(i), &*tmp);
+
+        while hole.pos > s && less(&tmp, &*hole.pos.sub(1)) {
+            hole.pos.write(hole.pos.sub(1).read());
+            hole.pos = hole.pos.sub(1);
+        }
+
+        count_moves(ptr_sub(s.add(i), hole.pos) + 1);
+        budget = budget.saturating_sub(ptr_sub(s.add(i), hole.pos));
+        i += 1;
+    }
+
+    i
+}
+
+// Sort `s..n` with a bottom-up merge sort, assuming the first `head` elements were already sorted
+// before runs of size `run` were created. Adjacent runs are merged with `merge`.
+unsafe fn merge_sort<T>(
+    s: *mut T,
+    head: usize,
+    n: usize,
+    run: usize,
+    merge: impl FnMut(*mut T, usize, usize),
 ) {
+    merge_sort_until(s, head, n, run, merge, || false);
+}
+
+// Sort `s..n` like `merge_sort`, calling `stop` between merge passes.
+//
+// Return `false` if `stop` returned `true`, leaving the sort unfinished.
+unsafe fn merge_sort_until<T>(
+    s: *mut T,
+    head: usize,
+    n: usize,
+    mut run: usize,
+    mut merge: impl FnMut(*mut T, usize, usize),
+    mut stop: impl FnMut() -> bool,
+) -> bool {
     while run < n {
         let mut l = head - head % (2 * run);
 
         while l + 2 * run <= n {
-            merge_in_place(s.add(l), run, run, less);
+            merge(s.add(l), run, run);
             l += 2 * run;
         }
 
         if l + run < n {
-            merge_in_place(s.add(l), run, n - (l + run), less);
+            merge(s.add(l), run, n - (l + run));
         }
 
         run *= 2;
+
+        if run < n && stop() {
+            return false;
+        }
     }
+
+    true
+}
+
+// Sort `s..s + n` with a merge sort that alternates merge passes between `s` and `scratch`, which
+// has room for `n` elements, assuming runs of size `run` were created. Every pass moves every
+// element once, and the result is copied back to `s` at the end if it ended up in `scratch`.
+#[cfg(feature = "alloc")]
+unsafe fn ping_pong_sort<T, F: Less<T>>(
+    s: *mut T,
+    n: usize,
+    mut run: usize,
+    scratch: *mut T,
+    opts: &SortOptions,
+    less: &mut F,
+) {
+    let (mut src, mut dst) = (s, scratch);
+
+    while run < n {
+        let mut pass = crate::merge::PingPong::new(s, src, dst, n);
+
+        for l in (0..n).step_by(2 * run) {
+            let (m, r) = (usize::min(l + run, n), usize::min(l + 2 * run, n));
+            pass.merge(m, r, opts, less);
+        }
+
+        count_moves(n);
+        run *= 2;
+
+        if run >= n {
+            // Dropping the finished pass moves the result back to `s` if needed
+            return;
+        }
+
+        core::mem::forget(pass);
+        (src, dst) = (dst, src);
+    }
+}
+
+// Sort `s..n` with a rotation-based merge sort, assuming the first `head` elements were already
+// sorted before runs of size `run` were created.
+unsafe fn merge_sort_in_place<T, F: Less<T>>(
+    s: *mut T,
+    head: usize,
+    n: usize,
+    run: usize,
+    opts: &SortOptions,
+    less: &mut F,
+) {
+    merge_sort(s, head, n, run, |s, n1, n2| {
+        merge_in_place(s, n1, n2, opts, less)
+    });
 }
 
 // Special sorting routine: use only rotation-based merging to sort in worst case `O(n log n)` time.
 // This avoids collecting an internal buffer.
-unsafe fn sort_special<T, F: Less<T>>(s: *mut T, n: usize, head: usize, tail: usize, less: &mut F) {
-    build_runs(s, s.add(head), n - tail, less);
-    merge_sort_in_place(s, head, n - tail, MIN_RUN, less);
+unsafe fn sort_special<S: SmallSort, T, F: Less<T>, O: FnMut(Event)>(
+    s: *mut T,
+    n: usize,
+    head: usize,
+    tail: usize,
+    opts: &SortOptions,
+    less: &mut F,
+    obs: &mut O,
+) {
+    obs(Event::FellBackToSpecial);
+    build_runs::<S, _, _, _>(s, s.add(head), n - tail, opts, less, obs);
+    merge_sort_in_place(s, head, n - tail, opts.min_run, opts, less);
 
     if tail > 0 {
-        build_runs(s, s.add(n - tail), n, less);
-        merge_sort_in_place(s, n - tail, n, MIN_RUN, less);
+        build_runs::<S, _, _, _>(s, s.add(n - tail), n, opts, less, obs);
+        merge_sort_in_place(s, n - tail, n, opts.min_run, opts, less);
     }
 }
 
 // Sort `s..buf.start` with block merge sort given `buf` as an internal buffer, assuming runs of
-// length `run` are already built on `0..tail_start`, and runs of length `MIN_RUN` are built on
+// length `run` are already built on `0..tail_start`, and runs of length `opts.min_run` are built on
 // `tail_start..`.
-unsafe fn block_merge_sort<T, F: Less<T>>(
+unsafe fn block_merge_sort<T, F: Less<T>, O: FnMut(Event)>(
     buf: &mut Buffer<T>,
     s: *mut T,
     head_run: usize,
     tail_start: usize,
+    opts: &SortOptions,
     less: &mut F,
+    obs: &mut O,
 ) {
     // Set up the buffer layout
     let mut block_len = buffer_block_length(buf.len);
-    let keys = buf.len - block_len + 1;
+    debug_assert!(block_len <= buf.len + 1);
+    obs(Event::BlockMergeStarted { block_len });
+    let keys = buf.len + 1 - block_len;
     buf.len = block_len - 1;
 
-    let mut run = MIN_RUN;
+    let mut run = opts.min_run;
     let n = ptr_sub(buf.start, s);
 
     // Block merging with merge buffer
@@ -128,16 +286,19 @@ unsafe fn block_merge_sort<T, F: Less<T>>(
         let mut l = tail_start * (run < head_run) as usize;
 
         while l + 2 * run <= n {
-            if !merge(buf, s.add(l), run, run, less) {
-                block_merge(buf, s.add(l), run, run, block_len, false, less);
+            if !merge(buf, s.add(l), run, run, opts, less) {
+                block_merge(buf, s.add(l), run, run, block_len, false, opts, less);
             }
 
             l += 2 * run;
         }
 
-        if l + run < n && !merge(buf, s.add(l), run, n - (l + run), less) {
-            l += block_merge(buf, s.add(l), run, n - (l + run), block_len, false, less);
-            merge(buf, s.add(l), n - l - n % block_len, n % block_len, less);
+        if l + run < n && !merge(buf, s.add(l), run, n - (l + run), opts, less) {
+            let n2 = n - (l + run);
+            l += block_merge(buf, s.add(l), run, n2, block_len, false, opts, less);
+
+            let rem = n % block_len;
+            merge(buf, s.add(l), n - l - rem, rem, opts, less);
         }
 
         run *= 2;
@@ -155,42 +316,309 @@ unsafe fn block_merge_sort<T, F: Less<T>>(
         let mut l = tail_start * (run < head_run) as usize;
 
         while l + 2 * run <= n {
-            block_merge(buf, s.add(l), run, run, block_len, true, less);
+            block_merge(buf, s.add(l), run, run, block_len, true, opts, less);
             l += 2 * run;
         }
 
-        if l + run + block_len < n {
-            l += block_merge(buf, s.add(l), run, n - (l + run), block_len, true, less);
+        if l + run + block_len <= n {
+            l += block_merge(buf, s.add(l), run, n - l - run, block_len, true, opts, less);
         }
 
-        merge_in_place(s.add(l), n - l - n % block_len, n % block_len, less);
+        merge_in_place(s.add(l), n - l - n % block_len, n % block_len, opts, less);
 
         run *= 2;
     }
 }
 
-/// Sort `s..s + n` with dustsort.
-pub unsafe fn sort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
+// Return the most comparisons `sort` may make on `n` elements with the default options and the
+// strategy `S`. Arrays shorter than `MIN_MERGE_SORT` are sorted by `S` alone, which is quadratic.
+//
+// Measured inputs peak just under 2048 elements with about `2 * sqrt n` distinct values, at 10
+// comparisons per element over `n * ceil(log2 n)` with insertion and 18 with `Network`.
+#[cfg(all(feature = "verify-bounds", debug_assertions))]
+fn max_comparisons<S: SmallSort>(n: usize) -> usize {
+    if n < MIN_MERGE_SORT {
+        return n * n;
+    }
+
+    let log = (usize::BITS - (n - 1).leading_zeros()) as usize;
+    n.saturating_mul(log + S::MAX_EXTRA_COMPARISONS)
+}
+
+/// Sort `s..s + n` with dustsort, using the strategy `S` to sort short runs.
+///
+/// Return `true` if `s..s + n` was already sorted, in which case it is left untouched.
+///
+/// With the `verify-bounds` feature in debug builds, panic if a sort with the default options makes
+/// more comparisons than `max_comparisons` allows.
+pub unsafe fn sort<S: SmallSort, T, F: Less<T>>(
+    s: *mut T,
+    n: usize,
+    opts: &SortOptions,
+    less: &mut F,
+) -> bool {
+    #[cfg(all(feature = "verify-bounds", debug_assertions))]
+    if *opts == SortOptions::DEFAULT && S::MAX_EXTRA_COMPARISONS < usize::MAX {
+        let mut cmps = 0;
+        let mut counted = |x: &T, y: &T| {
+            cmps += 1;
+            less(x, y)
+        };
+
+        let sorted = sort_observed::<S, _, _, _>(s, n, opts, &mut counted, &mut ignore);
+        let max = max_comparisons::<S>(n);
+        debug_assert!(
+            cmps <= max,
+            "sorting {n} elements took {cmps} comparisons, over {max}"
+        );
+        return sorted;
+    }
+
+    sort_observed::<S, _, _, _>(s, n, opts, less, &mut ignore)
+}
+
+/// Sort `s..s + n` like [`sort`], calling `obs` with each decision made along the way.
+pub unsafe fn sort_observed<S: SmallSort, T, F: Less<T>, O: FnMut(Event)>(
+    s: *mut T,
+    n: usize,
+    opts: &SortOptions,
+    less: &mut F,
+    obs: &mut O,
+) -> bool {
+    if n <= 1 {
+        return true;
+    }
+
+    let head = next_non_desc_run(s, n, less);
+
+    if head == n {
+        return true;
+    }
+
+    sort_unsorted::<S, _, _, _>(s, n, head, opts, less, obs);
+    false
+}
+
+/// Sort `s..s + n` with only rotation-based merging, using the strategy `S` to sort short runs.
+/// This never collects keys or uses a buffer.
+///
+/// Return `true` if `s..s + n` was already sorted, in which case it is left untouched.
+pub unsafe fn sort_in_place<S: SmallSort, T, F: Less<T>>(
+    s: *mut T,
+    n: usize,
+    opts: &SortOptions,
+    less: &mut F,
+) -> bool {
+    if n <= 1 {
+        return true;
+    }
+
+    let head = next_non_desc_run(s, n, less);
+
+    if head == n {
+        return true;
+    }
+
+    build_runs::<S, _, _, _>(s, s.add(head), n, opts, less, &mut ignore);
+    merge_sort_in_place(s, head, n, opts.min_run, opts, less);
+    false
+}
+
+/// Sort `s..s + n` with a plain merge sort, using the strategy `S` to sort short runs and merging
+/// through `scratch..scratch + cap` where the shorter run fits, or with rotations otherwise. Call
+/// `stop` between merge passes, and give up once it returns `true`.
+///
+/// Return `false` if the sort was given up, in which case `s..s + n` is left a permutation of its
+/// original elements.
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn sort_until<S: SmallSort, T, F: Less<T>, G: FnMut() -> bool>(
+    s: *mut T,
+    n: usize,
+    scratch: *mut T,
+    cap: usize,
+    opts: &SortOptions,
+    less: &mut F,
+    stop: G,
+) -> bool {
+    if n <= 1 {
+        return true;
+    }
+
+    let head = next_non_desc_run(s, n, less);
+
+    if head == n {
+        return true;
+    }
+
+    build_runs::<S, _, _, _>(s, s.add(head), n, opts, less, &mut ignore);
+
+    merge_sort_until(
+        s,
+        head,
+        n,
+        opts.min_run,
+        |s, n1, n2| merge_scratch(s, n1, n2, scratch, cap, opts, less),
+        stop,
+    )
+}
+
+/// Sort `s..s + n` with dustsort, using the strategy `S` to sort short runs and
+/// `scratch..scratch + cap` as an external merge buffer.
+///
+/// Return `true` if `s..s + n` was already sorted, in which case it is left untouched.
+pub unsafe fn sort_with_scratch<S: SmallSort, T, F: Less<T>>(
+    s: *mut T,
+    n: usize,
+    scratch: *mut T,
+    cap: usize,
+    opts: &SortOptions,
+    less: &mut F,
+) -> bool {
+    if n <= 1 {
+        return true;
+    }
+
+    let head = next_non_desc_run(s, n, less);
+
+    if head == n {
+        return true;
+    }
+
+    // Rotation-based merges on long runs cost more than collecting an internal buffer, so only use
+    // the scratch if every merge fits
+    if cap < n / 2 {
+        sort_unsorted::<S, _, _, _>(s, n, head, opts, less, &mut ignore);
+    } else {
+        scratch_merge_sort::<S, _, _, _>(s, n, head, scratch, cap, opts, less, &mut ignore);
+    }
+
+    false
+}
+
+// Sort `s..s + n` with a merge sort through `scratch..scratch + cap`, assuming the first `head`
+// elements are sorted.
+#[allow(clippy::too_many_arguments)]
+unsafe fn scratch_merge_sort<S: SmallSort, T, F: Less<T>, O: FnMut(Event)>(
+    s: *mut T,
+    n: usize,
+    head: usize,
+    scratch: *mut T,
+    cap: usize,
+    opts: &SortOptions,
+    less: &mut F,
+    obs: &mut O,
+) {
+    build_runs::<S, _, _, _>(s, s.add(head), n, opts, less, obs);
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if opts.simd_i32 {
+        // Only set by callers sorting `i32` in its natural order
+        let (s, scratch) = (s.cast::<i32>(), scratch.cast::<i32>());
+        return merge_sort(s, head, n, opts.min_run, |s, n1, n2| {
+            crate::merge::merge_scratch_i32(s, n1, n2, scratch, cap, opts)
+        });
+    }
+
+    merge_sort(s, head, n, opts.min_run, |s, n1, n2| {
+        merge_scratch(s, n1, n2, scratch, cap, opts, less)
+    });
+}
+
+// Sort `s..s + n` like `scratch_merge_sort`, given that half of it fits in a `StackScratch`. This is
+// kept out of line so that the buffer only takes up stack space when it's used.
+#[inline(never)]
+unsafe fn stack_merge_sort<S: SmallSort, T, F: Less<T>, O: FnMut(Event)>(
+    s: *mut T,
+    n: usize,
+    head: usize,
+    opts: &SortOptions,
+    less: &mut F,
+    obs: &mut O,
+) {
+    let mut scratch = StackScratch([MaybeUninit::uninit(); STACK_BUDGET]);
+    let cap = STACK_BUDGET / size_of::<T>().max(1);
+    let buf = scratch.0.as_mut_ptr().cast();
+    scratch_merge_sort::<S, _, _, _>(s, n, head, buf, cap, opts, less, obs);
+}
+
+// Sort `s..s + n`, given that its longest non-descending prefix has length `head < n`.
+unsafe fn sort_unsorted<S: SmallSort, T, F: Less<T>, O: FnMut(Event)>(
+    s: *mut T,
+    n: usize,
+    mut head: usize,
+    opts: &SortOptions,
+    less: &mut F,
+    obs: &mut O,
+) {
     if n < MIN_SCAN {
-        return insert_sort(s, 1, n, less);
+        return S::sort_small(s, head, n, less);
     }
 
-    let mut head = next_sorted_run(s, n, less);
+    head = extend_sorted_run(s, head, n, less);
     head += next_non_desc_run(s.add(head - 1), n - (head - 1), less) - 1;
 
     if head == n {
         return;
     }
 
-    if n < MIN_MERGE_SORT {
-        return insert_sort(s, head, n, less);
+    if n < opts.min_merge_sort {
+        return S::sort_small(s, head, n, less);
     }
 
+    // For a few appended elements, binary insertion saves comparisons over shifting them linearly
+    if n - head <= MAX_INSERT_TAIL {
+        return insert_sort_binary(s, head, n, less);
+    }
+
+    // For arrays with few inversions, try to finish in `O(n + inversions)` time
+    let samples = usize::min(n - head, LOW_INVERSION_SAMPLES);
+
+    if sampled_non_desc(s.add(head), n - head, samples, less) {
+        head = insert_sort_bounded(s, head, n, n.saturating_mul(MAX_AVG_SHIFT), less);
+
+        if head == n {
+            return;
+        }
+    }
+
+    // `n` is at most `isize::MAX` since ZSTs are never sorted, so `n + 1` can't overflow
     let block_len = array_block_length(n + 1);
+    obs(Event::RunBuilt { len: head });
 
     // For small appended tails, sort immediately with rotations
     if head + block_len * MAX_APPEND_BLOCKS >= n {
-        return sort_special(s, n, head, 0, less);
+        return sort_special::<S, _, _, _>(s, n, head, 0, opts, less, obs);
+    }
+
+    // Merging through a buffer is cheaper than collecting keys
+    if n / 2 * size_of::<T>() <= STACK_BUDGET && align_of::<T>() <= align_of::<StackScratch>() {
+        return stack_merge_sort::<S, _, _, _>(s, n, head, opts, less, obs);
+    }
+
+    #[cfg(feature = "alloc")]
+    {
+        let mut scratch = Vec::<MaybeUninit<T>>::new();
+        let ping_pong = size_of::<T>() <= MAX_PING_PONG_SIZE;
+
+        // Vectorized merges beat ping-ponging on large arrays
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        let ping_pong = ping_pong && !opts.simd_i32;
+
+        if ping_pong && scratch.try_reserve_exact(n).is_ok() {
+            let buf = scratch.spare_capacity_mut().as_mut_ptr().cast();
+            build_runs::<S, _, _, _>(s, s.add(head), n, opts, less, obs);
+
+            // Passes would copy the sorted head over and over, so merge it in once at the end
+            let h = head - head % opts.min_run;
+            ping_pong_sort(s.add(h), n - h, opts.min_run, buf, opts, less);
+            return merge_scratch(s, h, n - h, buf, n, opts, less);
+        }
+
+        if scratch.try_reserve_exact(n / 2).is_ok() {
+            let spare = scratch.spare_capacity_mut();
+            let (buf, cap) = (spare.as_mut_ptr().cast(), spare.len());
+            return scratch_merge_sort::<S, _, _, _>(s, n, head, buf, cap, opts, less, obs);
+        }
     }
 
     let mut buf = Buffer {
@@ -199,19 +627,19 @@ pub unsafe fn sort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
         unsorted: 0,
     };
 
-    buf.binary_find_keys(s.add(head), s.add(n), 12, less);
+    buf.binary_find_keys(s.add(head), s.add(n), opts.min_distinct, less);
 
     // For many similar items excluding head, sort immediately with rotations
-    if buf.len < MIN_DISTINCT {
+    if buf.len < opts.min_distinct {
         buf.shift(s.add(n - buf.len));
-        return sort_special(s, n, head, n - head, less);
+        return sort_special::<S, _, _, _>(s, n, head, n - head, opts, less, obs);
     }
 
     // Combine both cases above
     if buf.start <= s.add(head + block_len * MAX_APPEND_BLOCKS) {
         let tail = ptr_sub(s.add(n), buf.start);
         buf.shift(s.add(n - buf.len));
-        return sort_special(s, n, head, tail, less);
+        return sort_special::<S, _, _, _>(s, n, head, tail, opts, less, obs);
     }
 
     // Ideal number of buffer elements to guarantee all merges are buffered
@@ -227,19 +655,20 @@ pub unsafe fn sort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
             head -= buf.len - tmp_len;
         }
 
+        obs(Event::KeysCollected { count: buf.len });
         buf.shift(s.add(n - buf.len));
-        build_runs(s, s.add(head), n - buf.len, less);
-        block_merge_sort(&mut buf, s, MIN_RUN, 0, less);
-        merge_in_place(s, n - buf.len, buf.len, less);
+        build_runs::<S, _, _, _>(s, s.add(head), n - buf.len, opts, less, obs);
+        block_merge_sort(&mut buf, s, opts.min_run, 0, opts, less, obs);
+        merge_in_place(s, n - buf.len, buf.len, opts, less);
 
         return;
     }
 
     let mut l = ptr_sub(buf.start, s);
     let mut r = l + buf.len;
-    let mut run = MIN_RUN;
+    let mut run = opts.min_run;
 
-    build_runs(s, s.add(head), l, less);
+    build_runs::<S, _, _, _>(s, s.add(head), l, opts, less, obs);
 
     // Collect distinct keys
     while l > 0 {
@@ -254,7 +683,7 @@ pub unsafe fn sort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
         if buf.len >= run {
             // Merge pass
             for i in (2 * run..(l + 1)).step_by(2 * run) {
-                merge(&mut buf, s.add(i - 2 * run), run, run, less);
+                merge(&mut buf, s.add(i - 2 * run), run, run, opts, less);
             }
 
             buf.sort(less);
@@ -262,6 +691,8 @@ pub unsafe fn sort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
         }
     }
 
+    obs(Event::KeysCollected { count: buf.len });
+
     // Align buffer to the right
     buf.shift(s.add(n - buf.len));
 
@@ -276,12 +707,19 @@ pub unsafe fn sort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
     while l < r {
         let cur = next_non_desc_run(s.add(l), r - l, less);
 
-        if run > MIN_RUN && cur <= run / 2 && prev + cur <= run {
+        if run > opts.min_run && cur <= run / 2 && prev + cur <= run {
             run /= 2;
         }
 
         frag &= run - 1;
-        merge(&mut buf, s.add(l - frag), frag, cur.min(run - frag), less);
+        merge(
+            &mut buf,
+            s.add(l - frag),
+            frag,
+            cur.min(run - frag),
+            opts,
+            less,
+        );
 
         l += cur;
         frag += cur;
@@ -289,9 +727,9 @@ pub unsafe fn sort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
     }
 
     buf.sort(less);
-    build_runs(s, s.add(r), n - buf.len, less);
+    build_runs::<S, _, _, _>(s, s.add(r), n - buf.len, opts, less, obs);
 
     // Now we have runs in non-ascending powers of two e.g. `256 128 128 64 64 64 32 ...`
-    block_merge_sort(&mut buf, s, run, tail_start, less);
-    merge_in_place(s, n - buf.len, buf.len, less);
+    block_merge_sort(&mut buf, s, run, tail_start, opts, less, obs);
+    merge_in_place(s, n - buf.len, buf.len, opts, less);
 }
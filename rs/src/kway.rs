@@ -0,0 +1,65 @@
+use alloc::collections::{binary_heap::PeekMut, BinaryHeap};
+use core::cmp::Ordering;
+
+// The unmerged remainder of one input, ordered so that the heap yields the smallest head first,
+// breaking ties by input order
+struct Cursor<'a, T> {
+    // Remaining elements of the input, never empty while in the heap
+    rest: &'a [T],
+
+    // Index of the input
+    input: usize,
+}
+
+impl<T: Ord> Ord for Cursor<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&other.rest[0], other.input).cmp(&(&self.rest[0], self.input))
+    }
+}
+
+impl<T: Ord> PartialOrd for Cursor<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> PartialEq for Cursor<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Cursor<'_, T> {}
+
+/// Stably merge the sorted slices `inputs` into `out`, breaking ties by input order and then by
+/// position. This takes `O(n log k)` time for `n` elements in `k` inputs.
+///
+/// Panics if `out.len()` is not the total length of `inputs`.
+pub fn merge_slices<T: Ord + Clone>(inputs: &[&[T]], out: &mut [T]) {
+    let total = inputs.iter().map(|v| v.len()).sum::<usize>();
+    assert_eq!(
+        out.len(),
+        total,
+        "output length must match total input length"
+    );
+
+    let mut heap = inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, rest)| !rest.is_empty())
+        .map(|(input, &rest)| Cursor { rest, input })
+        .collect::<BinaryHeap<_>>();
+
+    for dst in out {
+        let Some(mut cur) = heap.peek_mut() else {
+            break;
+        };
+
+        dst.clone_from(&cur.rest[0]);
+        cur.rest = &cur.rest[1..];
+
+        if cur.rest.is_empty() {
+            PeekMut::pop(cur);
+        }
+    }
+}
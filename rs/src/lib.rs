@@ -1,40 +1,1234 @@
 #![no_std]
 
-use core::cmp::Ordering;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(any(test, feature = "stats"))]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+use core::{cell::Cell, cmp::Ordering, mem::MaybeUninit};
+
+use small_sort::{LinearInsertion, SmallSort};
+
+#[cfg(feature = "alloc")]
+pub use kway::merge_slices;
+pub use observe::{sort_with_observer, Event};
+pub use options::SortOptions;
+#[cfg(feature = "parallel")]
+pub use parallel::{par_sort, Spawn};
+pub use perm::{
+    apply_permutation, sort_by_cached_key, sort_by_precomputed_key, sort_indices_into,
+    sort_indices_into_usize,
+};
+#[cfg(feature = "alloc")]
+pub use perm::{argsort, argsort_usize, sort_by_key_collect, sort_by_small_rank, sort_with_undo};
+pub use slice::DustSortSlice;
+#[cfg(feature = "stats")]
+pub use stats::{sort_instrumented, SortStats};
+#[cfg(feature = "verify")]
+pub use verify::sort_checked;
 
 mod blocks;
 mod buffer;
+#[cfg(feature = "c-api")]
+pub mod capi;
 mod dust;
+#[cfg(feature = "alloc")]
+mod kway;
 mod merge;
+mod observe;
+mod options;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod partition;
+mod perm;
+pub mod raw;
+mod records;
 mod scan;
+mod slice;
+pub mod small_sort;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(test)]
+mod tests;
+mod unstable;
 mod util;
+#[cfg(feature = "verify")]
+mod verify;
 
 /// Sort `v`.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort<T: Ord>(v: &mut [T]) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut T::lt)
+}
+
+/// Sort `v` and return whether any element moved.
+///
+/// This is `false` exactly when `v` was already in non-descending order. Any other input, including
+/// a strictly descending one, has some element greater than the next, so stably sorting it moves
+/// at least one element.
 #[inline(always)]
-pub fn sort<T: Ord>(v: &mut [T]) {
-    sort_common(v, &mut T::lt);
+pub fn sort_detect_changes<T: Ord>(v: &mut [T]) -> bool {
+    !sort(v)
+}
+
+/// Sort `v` and return the number of inversions it had, i.e. the number of pairs of elements that
+/// were out of order, such as for computing Kendall's tau.
+///
+/// Runs are sorted with binary insertion and merged pairwise, counting the elements each one
+/// passes. This takes `O(n log n)` comparisons, about twice as many as [`sort`]. The count
+/// saturates at `u64::MAX`, which is only reachable beyond `2^33` elements.
+///
+/// ```
+/// let mut v = [3, 1, 2, 2, 0];
+/// assert_eq!(dustsort::sort_count_inversions(&mut v), 7);
+/// assert_eq!(v, [0, 1, 2, 2, 3]);
+/// ```
+#[inline(always)]
+pub fn sort_count_inversions<T: Ord>(v: &mut [T]) -> u64 {
+    sort_count_inversions_common(v, &mut T::lt)
 }
 
 /// Sort `v` with a comparator `compare`.
+///
+/// Return `true` if `v` was already sorted with respect to `compare`.
+///
+/// `compare` is never called with the same element as both arguments. Either reference may point
+/// to a temporary copy of an element rather than its slot in `v`, so state kept across calls should
+/// not be keyed by address.
+///
+/// ```
+/// let mut v = [5, 3, 8, 1, 9, 2, 7];
+/// let mut calls = 0;
+///
+/// dustsort::sort_by(&mut v, |x, y| {
+///     assert!(!core::ptr::eq(x, y));
+///     calls += 1;
+///     x.cmp(y)
+/// });
+///
+/// assert_eq!(v, [1, 2, 3, 5, 7, 8, 9]);
+/// assert!(calls > 0);
+/// ```
 #[inline(always)]
-pub fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
-    sort_common(v, &mut |x, y| compare(x, y) == Ordering::Less);
+pub fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| {
+        compare(x, y) == Ordering::Less
+    })
 }
 
 /// Sort `v` with a key extraction function `f`.
+///
+/// Return `true` if `v` was already sorted with respect to `f`.
+#[inline(always)]
+pub fn sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| f(x).lt(&f(y)))
+}
+
+/// Sort `v` with a key extraction function `f` that borrows the key from each element, so keys
+/// such as strings are compared without being cloned.
+///
+/// Return `true` if `v` was already sorted with respect to `f`.
+#[inline(always)]
+pub fn sort_by_key_ref<T, K: Ord + ?Sized, F: for<'a> FnMut(&'a T) -> &'a K>(
+    v: &mut [T],
+    mut f: F,
+) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| f(x).lt(f(y)))
+}
+
+/// A key extraction function that only needs shared access to itself, so one value can be stored
+/// and reused across many sorts. This is implemented for every `Fn(&T) -> K`.
+pub trait KeyFn<T, K>: Fn(&T) -> K {}
+impl<T, K, F: Fn(&T) -> K> KeyFn<T, K> for F {}
+
+/// Sort `v` with a key extraction function `f`, like [`sort_by_key`] but requiring only a [`KeyFn`].
+/// A stored extractor can be passed by reference, or by value if it's `Copy`.
+///
+/// Return `true` if `v` was already sorted with respect to `f`.
+#[inline(always)]
+pub fn sort_by_key_fn<T, K: Ord, F: KeyFn<T, K>>(v: &mut [T], f: F) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| f(x).lt(&f(y)))
+}
+
+/// Sort `v` by a categorical rank `rank`, such as an index into a fixed list of categories. This is
+/// [`sort_by_key`] with `u32` keys. If the largest rank is known and small, `sort_by_small_rank`
+/// sorts in linear time instead.
+///
+/// Return `true` if `v` was already sorted by rank.
+#[inline(always)]
+pub fn sort_by_rank<T, F: FnMut(&T) -> u32>(v: &mut [T], rank: F) -> bool {
+    sort_by_key(v, rank)
+}
+
+/// Sort `v` in descending order. Equal elements keep their original order.
+///
+/// Return `true` if `v` was already sorted in descending order, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_desc<T: Ord>(v: &mut [T]) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| y.lt(x))
+}
+
+/// Sort `v` in descending order of a key extraction function `f`. Equal elements keep their
+/// original order.
+///
+/// Return `true` if `v` was already sorted in descending order with respect to `f`.
+#[inline(always)]
+pub fn sort_desc_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| f(y).lt(&f(x)))
+}
+
+/// Sort `v` with a key extraction function `f`, putting elements with equal keys in the reverse of
+/// their original order, so that the last occurrence of each key comes first.
+///
+/// This reverses `v` and then sorts it stably, so it allocates nothing.
+///
+/// Return `true` if the keys of `v` were already strictly ascending, in which case it is left
+/// untouched.
+#[inline(always)]
+pub fn sort_reverse_stable_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) -> bool {
+    if first_unsorted_index_by(v, |x, y| f(x).le(&f(y))).is_none() {
+        return true;
+    }
+
+    v.reverse();
+    sort_by_key(v, f);
+    false
+}
+
+/// Sort `v` with a strict "less than" predicate `less`, called through dynamic dispatch.
+///
+/// Unlike the other sorts, which are compiled anew for every comparator, this compiles the sort only
+/// once per element type, which saves code size when sorting the same type in many different ways.
+/// Each comparison costs an indirect call in exchange.
+///
+/// Return `true` if `v` was already sorted with respect to `less`.
+#[inline(never)]
+pub fn sort_by_dyn<T>(v: &mut [T], mut less: &mut dyn FnMut(&T, &T) -> bool) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut less)
+}
+
+/// Sort `v` with a strict "less than" predicate `less` that reads shared context `ctx`, such as a
+/// collation table, on every comparison.
+///
+/// This saves capturing `ctx` in a closure, so one plain `fn` can sort with many contexts.
+///
+/// Return `true` if `v` was already sorted with respect to `less`.
+///
+/// ```
+/// // Order letters by a collation table, e.g. one placing vowels first
+/// fn less(rank: &[u8; 26], x: &&str, y: &&str) -> bool {
+///     let key = |s: &str| s.bytes().map(|b| rank[(b - b'a') as usize]).collect::<Vec<_>>();
+///     key(x) < key(y)
+/// }
+///
+/// let mut rank = [0; 26];
+///
+/// for (i, b) in b"aeioubcdfghjklmnpqrstvwxyz".iter().enumerate() {
+///     rank[(b - b'a') as usize] = i as u8;
+/// }
+///
+/// let mut v = ["bee", "cab", "ace", "ebb"];
+/// dustsort::sort_by_with_ctx(&mut v, &rank, less);
+/// assert_eq!(v, ["ace", "ebb", "bee", "cab"]);
+/// ```
+#[inline(always)]
+pub fn sort_by_with_ctx<T, C: ?Sized, F: FnMut(&C, &T, &T) -> bool>(
+    v: &mut [T],
+    ctx: &C,
+    mut less: F,
+) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| less(ctx, x, y))
+}
+
+/// Sort `v` using only rotation-based merging, never collecting keys or using a buffer.
+///
+/// The strategy doesn't depend on how many distinct elements `v` has or on available memory, and
+/// elements only move by rotations within `v`. In exchange, this is slower than [`sort`], taking up
+/// to `O(n log^2 n)` time.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_in_place<T: Ord>(v: &mut [T]) -> bool {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return true;
+    }
+
+    let (s, n, opts) = (v.as_mut_ptr(), v.len(), &SortOptions::DEFAULT);
+    unsafe { dust::sort_in_place::<LinearInsertion, _, _>(s, n, opts, &mut T::lt) }
+}
+
+/// Sort `v` by a partial order, without panicking if it is inconsistent.
+///
+/// If `T::lt` is a strict weak order on the elements of `v`, this is the same as [`sort`].
+/// Otherwise, such as with NaN among floats or with a non-transitive order, the sort still
+/// terminates and leaves `v` as some permutation of its original elements, though not necessarily
+/// sorted.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_lenient<T: PartialOrd>(v: &mut [T]) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT.lenient(true), &mut T::lt)
+}
+
+/// Sort `v` with a C-style comparator `compare`, which returns a negative value if its first
+/// argument is less than its second, like the comparators taken by `qsort`.
+///
+/// Comparators ported from C are often inconsistent, e.g. by returning `a - b` where the subtraction
+/// can overflow. Instead of panicking with "Ord violated", this sorts like [`sort_lenient`]: the
+/// sort still terminates and leaves `v` as some permutation of its original elements, though not
+/// necessarily sorted.
+///
+/// Return `true` if `v` was already sorted with respect to `compare`.
+///
+/// ```
+/// let mut v = [3, 1, 2];
+/// dustsort::sort_by_raw(&mut v, |x, y| x - y);
+/// assert_eq!(v, [1, 2, 3]);
+///
+/// // Claiming every element is less than every other can't be satisfied
+/// let mut v = [5, 4, 3, 2, 1, 0, 9, 8, 7, 6];
+/// dustsort::sort_by_raw(&mut v, |_, _| -1);
+/// v.sort();
+/// assert_eq!(v, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+/// ```
+#[inline(always)]
+pub fn sort_by_raw<T, F: FnMut(&T, &T) -> i32>(v: &mut [T], mut compare: F) -> bool {
+    let opts = &SortOptions::DEFAULT.lenient(true);
+    sort_common::<LinearInsertion, _, _>(v, opts, &mut |x, y| compare(x, y) < 0)
+}
+
+/// Sort `v`, like [`sort`]. With the `simd` feature on x86-64, runs are merged four elements at a
+/// time.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_i32(v: &mut [i32]) -> bool {
+    sort_i32_common(v)
+}
+
+/// Sort `v`, like [`sort`]. With the `simd` feature on x86-64, runs are merged four elements at a
+/// time.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_u32(v: &mut [u32]) -> bool {
+    // `u32` and `i32` share a layout, and every bit pattern is valid for both
+    let v = unsafe { &mut *(v as *mut [u32] as *mut [i32]) };
+
+    // Flipping the sign bit maps the order of `u32` onto that of `i32`
+    sort_by_i32_key(v, |x| x ^ i32::MIN)
+}
+
+/// Sort `v` by the total order of [`f32::total_cmp`]: negative NaNs first, then negative infinity
+/// through `-0.0`, then `0.0` through infinity, then positive NaNs.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_floats_f32(v: &mut [f32]) -> bool {
+    // `f32` and `i32` share a layout, and every bit pattern is valid for both
+    let v = unsafe { &mut *(v as *mut [f32] as *mut [i32]) };
+
+    // As in `f32::total_cmp`, flipping all but the sign bit of negative values orders the bits
+    sort_by_i32_key(v, |x| x ^ (((x >> 31) as u32) >> 1) as i32)
+}
+
+/// Sort `v` by the total order of [`f64::total_cmp`]: negative NaNs first, then negative infinity
+/// through `-0.0`, then `0.0` through infinity, then positive NaNs.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_floats_f64(v: &mut [f64]) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| {
+        x.total_cmp(y) == Ordering::Less
+    })
+}
+
+/// Sort `v` with a fallible comparator `compare`.
+///
+/// If `compare` returns an error, the sort stops comparing and returns the first error, leaving `v`
+/// as some permutation of its original elements.
+pub fn try_sort_by<T, E, F: FnMut(&T, &T) -> Result<Ordering, E>>(
+    v: &mut [T],
+    mut compare: F,
+) -> Result<(), E> {
+    let mut err = None;
+    let opts = SortOptions::DEFAULT.lenient(true);
+
+    // After an error, treat all elements as equal so the sort winds down without reordering. The
+    // answers no longer agree with earlier ones, so the sort must be lenient.
+    sort_common::<LinearInsertion, _, _>(v, &opts, &mut |x, y| {
+        err.is_none()
+            && match compare(x, y) {
+                Ok(ord) => ord == Ordering::Less,
+                Err(e) => {
+                    err = Some(e);
+                    false
+                }
+            }
+    });
+
+    err.map_or(Ok(()), Err)
+}
+
+/// Sort `v` without preserving the order of equal elements.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_unstable<T: Ord>(v: &mut [T]) -> bool {
+    sort_unstable_common(v, &mut T::lt)
+}
+
+/// Sort `v` with a comparator `compare` without preserving the order of equal elements.
+///
+/// Return `true` if `v` was already sorted with respect to `compare`.
+#[inline(always)]
+pub fn sort_unstable_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) -> bool {
+    sort_unstable_common(v, &mut |x, y| compare(x, y) == Ordering::Less)
+}
+
+/// Sort `v` with a key extraction function `f` without preserving the order of equal elements.
+///
+/// Return `true` if `v` was already sorted with respect to `f`.
+#[inline(always)]
+pub fn sort_unstable_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) -> bool {
+    sort_unstable_common(v, &mut |x, y| f(x).lt(&f(y)))
+}
+
+/// Sort `v` with a comparator `compare`, using the strategy `S` to sort short runs.
+///
+/// Return `true` if `v` was already sorted with respect to `compare`.
+#[inline(always)]
+pub fn sort_by_with<S: SmallSort, T, F: FnMut(&T, &T) -> Ordering>(
+    v: &mut [T],
+    mut compare: F,
+) -> bool {
+    sort_common::<S, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| {
+        compare(x, y) == Ordering::Less
+    })
+}
+
+/// Sort `v` with a strict "less than" predicate `less`, using `scratch` as an external merge
+/// buffer. A `scratch` shorter than half the length of `v` is ignored.
+///
+/// Return `true` if `v` was already sorted with respect to `less`.
+#[inline(always)]
+pub fn sort_with_scratch<T, F: FnMut(&T, &T) -> bool>(
+    v: &mut [T],
+    scratch: &mut [MaybeUninit<T>],
+    mut less: F,
+) -> bool {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return true;
+    }
+
+    let (s, n) = (v.as_mut_ptr(), v.len());
+    let (buf, cap) = (scratch.as_mut_ptr().cast(), scratch.len());
+    let opts = &SortOptions::DEFAULT;
+
+    unsafe { dust::sort_with_scratch::<LinearInsertion, _, _>(s, n, buf, cap, opts, &mut less) }
+}
+
+/// Sort the initialized prefix `buf[..init_len]` in-place. The rest of `buf` is never read or
+/// written, not even as scratch memory.
+///
+/// Return `true` if the prefix was already sorted, in which case it is left untouched.
+///
+/// Panics if `init_len > buf.len()`.
+///
+/// # Safety
+///
+/// The elements `buf[..init_len]` must be initialized.
+#[inline(always)]
+pub unsafe fn sort_initialized<T: Ord>(buf: &mut [MaybeUninit<T>], init_len: usize) -> bool {
+    let s: *mut T = buf[..init_len].as_mut_ptr().cast();
+    sort(core::slice::from_raw_parts_mut(s, init_len))
+}
+
+/// Sort `v` with a strict "less than" predicate `less`, calling `stop` on the partially sorted `v`
+/// between merge passes and giving up once it returns `true`.
+///
+/// This merges runs pass by pass, through a buffer of half the length of `v` if the `alloc` feature
+/// is enabled, or with rotations otherwise. Each pass doubles the length of the sorted runs.
+///
+/// Return `true` if the sort finished, or `false` if `stop` cut it short, in which case `v` is left
+/// a permutation of its original elements.
+pub fn sort_until<T, F: FnMut(&T, &T) -> bool, G: FnMut(&[T]) -> bool>(
+    v: &mut [T],
+    mut less: F,
+    mut stop: G,
+) -> bool {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return true;
+    }
+
+    let (s, n) = (v.as_mut_ptr(), v.len());
+
+    #[cfg(feature = "alloc")]
+    let mut alloc = Vec::<MaybeUninit<T>>::new();
+    #[cfg(feature = "alloc")]
+    let scratch = match alloc.try_reserve_exact(n / 2) {
+        Ok(()) => alloc.spare_capacity_mut(),
+        Err(_) => &mut [],
+    };
+
+    #[cfg(not(feature = "alloc"))]
+    let scratch: &mut [MaybeUninit<T>] = &mut [];
+
+    let (buf, cap) = (scratch.as_mut_ptr().cast(), scratch.len());
+    let opts = &SortOptions::DEFAULT;
+
+    unsafe {
+        let stop = || stop(core::slice::from_raw_parts(s, n));
+        dust::sort_until::<LinearInsertion, _, _, _>(s, n, buf, cap, opts, &mut less, stop)
+    }
+}
+
+/// Sort `v` with a strict "less than" predicate `less`, spending one unit of `fuel` per comparison
+/// and giving up once it runs out, so a long sort can yield to other work.
+///
+/// `fuel` is only checked between merge passes, like the `stop` callback of [`sort_until`], so the
+/// initial short runs and the first merge pass are always done. Once it reaches zero, comparisons
+/// go on uncharged until the current pass ends, so the sort can overrun its budget. Nothing is
+/// saved between calls: sorting `v` again with more fuel starts over on the partially sorted `v`.
+///
+/// Return `true` if the sort finished, even if it used up `fuel`, or `false` if it was cut short, in
+/// which case `v` is left a permutation of its original elements and `fuel` is zero.
+#[inline(always)]
+pub fn sort_by_fueled<T, F: FnMut(&T, &T) -> bool>(
+    v: &mut [T],
+    mut less: F,
+    fuel: &mut u64,
+) -> bool {
+    let fuel = Cell::from_mut(fuel);
+
+    let less = |x: &T, y: &T| {
+        fuel.set(fuel.get().saturating_sub(1));
+        less(x, y)
+    };
+
+    sort_until(v, less, |_| fuel.get() == 0)
+}
+
+/// Sort `v` with a strict "less than" predicate `less`, using the tuning thresholds in `opts`.
+///
+/// Return `true` if `v` was already sorted with respect to `less`.
+#[inline(always)]
+pub fn sort_with_options<T, F: FnMut(&T, &T) -> bool>(
+    v: &mut [T],
+    opts: SortOptions,
+    mut less: F,
+) -> bool {
+    sort_common::<LinearInsertion, _, _>(v, &opts, &mut less)
+}
+
+/// Return whether `v` is sorted.
+#[inline(always)]
+pub fn is_sorted<T: Ord>(v: &[T]) -> bool {
+    is_sorted_by(v, T::lt)
+}
+
+/// Return whether `v` is sorted with respect to a strict "less than" predicate `less`.
+#[inline(always)]
+pub fn is_sorted_by<T, F: FnMut(&T, &T) -> bool>(v: &[T], mut less: F) -> bool {
+    unsafe { scan::next_non_desc_run(v.as_ptr(), v.len(), &mut less) == v.len() }
+}
+
+/// Return the first index `i` where `v[i] < v[i - 1]`, or `None` if `v` is sorted.
+#[inline(always)]
+pub fn first_unsorted_index<T: Ord>(v: &[T]) -> Option<usize> {
+    first_unsorted_index_by(v, T::lt)
+}
+
+/// Return the first index `i` where `less(&v[i], &v[i - 1])`, or `None` if `v` is sorted with
+/// respect to a strict "less than" predicate `less`.
+#[inline(always)]
+pub fn first_unsorted_index_by<T, F: FnMut(&T, &T) -> bool>(v: &[T], mut less: F) -> Option<usize> {
+    let i = unsafe { scan::next_non_desc_run(v.as_ptr(), v.len(), &mut less) };
+    (i < v.len()).then_some(i)
+}
+
+/// Return the first index `i` where `f(&v[i]) < f(&v[i - 1])`, or `None` if `v` is sorted with
+/// respect to a key extraction function `f`.
+#[inline(always)]
+pub fn first_unsorted_index_by_key<T, K: Ord, F: FnMut(&T) -> K>(
+    v: &[T],
+    mut f: F,
+) -> Option<usize> {
+    first_unsorted_index_by(v, |x, y| f(x).lt(&f(y)))
+}
+
+/// Binary search the sorted `v` for `x`.
+///
+/// Return `Ok` with the index of the first element equal to `x` if there is one, or `Err` with the
+/// index where `x` could be inserted to keep `v` sorted otherwise.
+#[inline(always)]
+pub fn binary_search<T: Ord>(v: &[T], x: &T) -> Result<usize, usize> {
+    binary_search_by(v, |y| y.cmp(x))
+}
+
+/// Binary search the sorted `v` with a comparator `f`, which returns the order of an element
+/// relative to the target, like [`binary_search`].
+#[inline(always)]
+pub fn binary_search_by<T, F: FnMut(&T) -> Ordering>(v: &[T], mut f: F) -> Result<usize, usize> {
+    let i = util::lower_bound(v.len(), |i| f(&v[i]) == Ordering::Less);
+
+    match v.get(i).map(f) {
+        Some(Ordering::Equal) => Ok(i),
+        _ => Err(i),
+    }
+}
+
+/// Binary search the sorted `v` for the key `b` with a key extraction function `f`, like
+/// [`binary_search`].
+#[inline(always)]
+pub fn binary_search_by_key<T, B: Ord, F: FnMut(&T) -> B>(
+    v: &[T],
+    b: &B,
+    mut f: F,
+) -> Result<usize, usize> {
+    binary_search_by(v, |x| f(x).cmp(b))
+}
+
+/// Return the subslice of the sorted `v` with the elements that are not less than `lo` but less than
+/// `hi`, found with two binary searches. If `lo > hi`, the subslice is empty.
+///
+/// ```
+/// let v = [1, 2, 2, 3, 3, 3, 5];
+/// assert_eq!(dustsort::sorted_range(&v, &2, &3), [2, 2]);
+/// assert_eq!(dustsort::sorted_range(&v, &2, &4), [2, 2, 3, 3, 3]);
+/// assert_eq!(dustsort::sorted_range(&v, &0, &9), v);
+/// assert!(dustsort::sorted_range(&v, &3, &2).is_empty());
+/// assert!(dustsort::sorted_range(&v, &6, &9).is_empty());
+/// ```
+#[inline(always)]
+pub fn sorted_range<'a, T: Ord>(v: &'a [T], lo: &T, hi: &T) -> &'a [T] {
+    let (s, n) = (v.as_ptr(), v.len());
+
+    // Everything from `l` on is at least `lo`, so it's also at least `hi` if `lo > hi`
+    let l = unsafe { util::search_left(s, n, lo, &mut T::lt) };
+    let r = l + unsafe { util::search_left(s.add(l), n - l, hi, &mut T::lt) };
+
+    &v[l..r]
+}
+
+/// Return the end index of each maximal non-descending run in `v`, in order, without modifying
+/// `v`. The last index is `v.len()` unless `v` is empty.
+///
+/// A sorted `v` is a single run, and a strictly descending `v` is a run per element.
+#[cfg(feature = "alloc")]
+pub fn detect_runs<T: Ord>(v: &[T]) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut i = 0;
+
+    while i < v.len() {
+        i += unsafe { scan::next_non_desc_run(v[i..].as_ptr(), v.len() - i, &mut T::lt) };
+        ends.push(i);
+    }
+
+    ends
+}
+
+/// Sort `v` and return the index range of each group of equal elements, in order. The ranges are
+/// adjacent and together cover `0..v.len()`.
+#[cfg(feature = "alloc")]
+pub fn sort_group<T: Ord>(v: &mut [T]) -> Vec<Range<usize>> {
+    sort(v);
+
+    let mut groups = Vec::new();
+    let mut i = 0;
+
+    while i < v.len() {
+        let j = (i + 1..v.len()).find(|&j| v[i] < v[j]).unwrap_or(v.len());
+        groups.push(i..j);
+        i = j;
+    }
+
+    groups
+}
+
+/// Reorder `v` so that the element at `index` is the one that would be there if sorted, with no
+/// greater elements before it and no smaller elements after it. The order of equal elements is not
+/// preserved.
+///
+/// Return the regions before and after `index`, and the element at `index`.
+///
+/// Panics if `index >= v.len()`.
+pub fn select_nth<T: Ord>(v: &mut [T], index: usize) -> (&mut [T], &mut T, &mut [T]) {
+    assert!(index < v.len(), "index >= len");
+
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() > 0 {
+        unsafe {
+            unstable::select(v.as_mut_ptr(), v.len(), index, &mut T::lt);
+        }
+    }
+
+    let (left, rest) = v.split_at_mut(index);
+    let (nth, right) = rest.split_first_mut().unwrap();
+    (left, nth, right)
+}
+
+/// Reorder `v` so that `v[..k]` holds its `k` smallest elements in sorted order. The order of the
+/// remaining elements is unspecified.
+#[inline(always)]
+pub fn partial_sort<T: Ord>(v: &mut [T], k: usize) {
+    partial_sort_common(v, k, &mut T::lt);
+}
+
+/// Reorder `v` so that `v[..k]` holds its `k` smallest elements in sorted order with respect to a
+/// comparator `compare`. The order of the remaining elements is unspecified.
+#[inline(always)]
+pub fn partial_sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], k: usize, mut compare: F) {
+    partial_sort_common(v, k, &mut |x, y| compare(x, y) == Ordering::Less);
+}
+
+/// Reorder `v` so that `v[..k]` holds its `k` smallest elements in sorted order with respect to a
+/// key extraction function `f`. The order of the remaining elements is unspecified.
+#[inline(always)]
+pub fn partial_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], k: usize, mut f: F) {
+    partial_sort_common(v, k, &mut |x, y| f(x).lt(&f(y)));
+}
+
+/// Reorder `v` so that it starts with its `k` smallest elements in sorted order, and return them.
+/// Equal elements keep their original order, so ties are won by the earliest ones. The order of the
+/// remaining elements is unspecified.
+#[inline(always)]
+pub fn k_smallest<T: Ord>(v: &mut [T], k: usize) -> &mut [T] {
+    k_smallest_common(v, k, &mut T::lt)
+}
+
+/// Reorder `v` so that it starts with its `k` largest elements in descending order, and return
+/// them. Equal elements keep their original order, so ties are won by the earliest ones. The order
+/// of the remaining elements is unspecified.
+#[inline(always)]
+pub fn k_largest<T: Ord>(v: &mut [T], k: usize) -> &mut [T] {
+    k_smallest_common(v, k, &mut |x, y| y.lt(x))
+}
+
+/// Sort `a` followed by `b` as one sequence, such as the two halves of a `VecDeque`, without
+/// moving elements between separate memory other than by swapping.
+///
+/// Return `true` if the sequence was already sorted, in which case it is left untouched.
+#[inline(always)]
+pub fn sort_split<T: Ord>(a: &mut [T], b: &mut [T]) -> bool {
+    sort_split_common(a, b, &mut T::lt)
+}
+
+/// Move the elements of `v` satisfying `pred` to the front, keeping the relative order of the
+/// elements on both sides. `pred` is called once per element.
+///
+/// Return the number of elements satisfying `pred`.
+#[inline(always)]
+pub fn stable_partition<T, F: FnMut(&T) -> bool>(v: &mut [T], mut pred: F) -> usize {
+    unsafe { partition::stable_partition(v.as_mut_ptr(), v.len(), &mut pred) }
+}
+
+/// Sort `v` by a boolean key `f`, stably placing the elements mapped to `false` before those mapped
+/// to `true`. `f` is called once per element and no comparisons are made, but merging the groups
+/// with rotations takes `O(n log n)` moves in the worst case.
+///
+/// Return the number of elements mapped to `false`.
+#[inline(always)]
+pub fn sort_bool_by<T, F: FnMut(&T) -> bool>(v: &mut [T], mut f: F) -> usize {
+    stable_partition(v, |x| !f(x))
+}
+
+/// Sort only the elements of `v` satisfying `keep` into a prefix, leaving the others after it in
+/// their original order. `keep` is called once per element, and no discarded element is compared.
+///
+/// Return the length of the sorted prefix.
+#[inline(always)]
+pub fn sort_retain<T: Ord, P: FnMut(&T) -> bool>(v: &mut [T], keep: P) -> usize {
+    let k = stable_partition(v, keep);
+    sort(&mut v[..k]);
+    k
+}
+
+/// Merge the sorted regions `v[..mid]` and `v[mid..]` in-place.
+///
+/// The merge is stable and uses no extra memory. It works by rotating blocks into place, which
+/// takes `O(n log n)` time in the worst case but favours few moves per element, so it suits large
+/// elements that are expensive to move. Regions shorter than 512 elements are merged with simple
+/// galloping rotations instead.
+///
+/// Panics if `mid > v.len()`.
+#[inline(always)]
+pub fn merge_sorted<T: Ord>(v: &mut [T], mid: usize) {
+    merge_common(v, mid, &mut T::lt);
+}
+
+/// Merge the sorted regions `v[..mid]` and `v[mid..]` in-place with a comparator `compare`.
+///
+/// Panics if `mid > v.len()`.
+#[inline(always)]
+pub fn merge_sorted_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mid: usize, mut compare: F) {
+    merge_common(v, mid, &mut |x, y| compare(x, y) == Ordering::Less);
+}
+
+/// Merge the sorted regions `v[..mid]` and `v[mid..]` in-place with a key extraction function `f`.
+///
+/// Panics if `mid > v.len()`.
+#[inline(always)]
+pub fn merge_sorted_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mid: usize, mut f: F) {
+    merge_common(v, mid, &mut |x, y| f(x).lt(&f(y)));
+}
+
+/// Move the last element of `v` into place, given that the rest of `v` is sorted, and return its
+/// new index. It's placed after any equal elements, so it stays put if no element is greater.
+///
+/// Panics if `v` is empty.
+#[inline(always)]
+pub fn insert_sorted<T: Ord>(v: &mut [T]) -> usize {
+    assert!(!v.is_empty(), "v is empty");
+
+    let (s, n) = (v.as_mut_ptr(), v.len() - 1);
+
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return n;
+    }
+
+    unsafe {
+        let i = util::search_right(s, n, s.add(n), &mut T::lt);
+
+        if i < n {
+            util::insert_left(s.add(n), n - i);
+        }
+
+        i
+    }
+}
+
+/// Merge the sorted runs of `v` in-place into one sorted sequence, where the runs are split at the
+/// indices in `boundaries`, such as those returned by `detect_runs`.
+///
+/// The merge is stable, and the runs are merged pairwise in a balanced order, so that merging `k`
+/// runs takes `O(n log k)` time with the `alloc` feature. Merges go through an allocated buffer of
+/// half the length of `v` if possible, and otherwise use the in-place merge of [`merge_sorted`].
+///
+/// Panics if `boundaries` is not strictly increasing or contains an index greater than `v.len()`.
+#[inline(always)]
+pub fn merge_runs<T: Ord>(v: &mut [T], boundaries: &[usize]) {
+    merge_runs_common(v, boundaries, &mut T::lt);
+}
+
+/// Sort `v`, given that it is already sorted between the indices in `run_boundaries`, by merging
+/// those runs directly instead of scanning for runs of its own.
+///
+/// This is [`merge_runs`] with the hint checked in debug builds, which panic with "run not sorted"
+/// if a run isn't sorted. Release builds trust the hint, and leave `v` in unspecified order if it
+/// is wrong.
+///
+/// Panics if `run_boundaries` is not strictly increasing or contains an index greater than
+/// `v.len()`.
+#[inline(always)]
+pub fn sort_hint_runs<T: Ord>(v: &mut [T], run_boundaries: &[usize]) {
+    if cfg!(debug_assertions) {
+        let mut l = 0;
+
+        // Skip past bad boundaries, leaving them for `merge_runs` to reject
+        for &r in run_boundaries.iter().chain([&v.len()]) {
+            assert!(v.get(l..r).is_none_or(is_sorted), "run not sorted");
+            l = l.max(r);
+        }
+    }
+
+    merge_runs(v, run_boundaries);
+}
+
+/// Append the elements of `new` to the sorted `v` and merge them into place, without sorting all of
+/// `v` again.
+///
+/// The new elements are sorted on their own, then merged into `v` through a buffer the length of the
+/// shorter side, or in place if it can't be allocated. Equal elements keep their order, with new ones after old
+/// ones. Debug builds panic with "prefix not sorted" if `v` wasn't sorted. Release builds skip that
+/// check, and leave `v` in unspecified order if it is wrong.
+#[cfg(feature = "alloc")]
+pub fn extend_sorted<T: Ord>(v: &mut Vec<T>, new: impl IntoIterator<Item = T>) {
+    let mid = v.len();
+    v.extend(new);
+
+    if v.len() == mid {
+        return;
+    }
+
+    debug_assert!(is_sorted(&v[..mid]), "prefix not sorted");
+    sort(&mut v[mid..]);
+
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    let (s, n) = (v.as_mut_ptr(), v.len());
+    let opts = &SortOptions::DEFAULT;
+
+    let mut scratch = Vec::<MaybeUninit<T>>::new();
+    let _ = scratch.try_reserve_exact(usize::min(mid, n - mid));
+    let spare = scratch.spare_capacity_mut();
+    let (buf, cap) = (spare.as_mut_ptr().cast(), spare.len());
+
+    unsafe { merge::merge_scratch(s, mid, n - mid, buf, cap, opts, &mut T::lt) }
+}
+
+/// Sort each consecutive chunk of `chunk` elements of `v` independently, calling `on_chunk` with
+/// each chunk once it is sorted. The last chunk may be shorter.
+///
+/// This is the first pass of an external merge sort: `on_chunk` can write each run out, and runs
+/// loaded back contiguously can be merged with [`merge_runs`] at the multiples of `chunk`.
+///
+/// Panics if `chunk == 0`.
+pub fn sort_chunks<T: Ord, F: FnMut(&[T])>(v: &mut [T], chunk: usize, mut on_chunk: F) {
+    assert!(chunk > 0, "chunk size must be non-zero");
+
+    for c in v.chunks_mut(chunk) {
+        sort(c);
+        on_chunk(c);
+    }
+}
+
+/// Sort `v` and move its distinct elements to the front, keeping the first occurrence of each.
+///
+/// Return the number of distinct elements. The duplicates are left after them in unspecified
+/// order.
+#[inline(always)]
+pub fn sort_dedup<T: Ord>(v: &mut [T]) -> usize {
+    sort_dedup_common(v, &mut T::lt)
+}
+
+/// Sort `v` with a key extraction function `f` and move the elements with distinct keys to the
+/// front, keeping the first occurrence of each.
+///
+/// Return the number of distinct keys. The duplicates are left after them in unspecified order.
+#[inline(always)]
+pub fn sort_dedup_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) -> usize {
+    sort_dedup_common(v, &mut |x, y| f(x).lt(&f(y)))
+}
+
+/// Stably sort the fixed-size records of length `record_len` in `page` by their leading `key_len`
+/// bytes, compared as unsigned lexicographic strings. Whole records are moved, and any incomplete
+/// record at the end of `page` is left untouched.
+pub fn sort_records(page: &mut [u8], record_len: usize, key_len: usize) {
+    // Ignore empty records
+    if record_len == 0 {
+        return;
+    }
+
+    debug_assert!(page.len().is_multiple_of(record_len));
+    debug_assert!(key_len <= record_len);
+
+    let n = page.len() / record_len;
+    let key_len = usize::min(key_len, record_len);
+
+    unsafe {
+        records::sort(page.as_mut_ptr(), n, record_len, key_len);
+    }
+}
+
+#[inline(always)]
+fn sort_common<S: SmallSort, T, F: FnMut(&T, &T) -> bool>(
+    v: &mut [T],
+    opts: &SortOptions,
+    less: &mut F,
+) -> bool {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return true;
+    }
+
+    unsafe { dust::sort::<S, _, _>(v.as_mut_ptr(), v.len(), opts, less) }
+}
+
+#[inline(always)]
+fn partial_sort_common<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], k: usize, less: &mut F) {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 || k == 0 {
+        return;
+    }
+
+    let (s, n) = (v.as_mut_ptr(), v.len());
+
+    unsafe {
+        // Gather the `k` smallest elements in front, then sort only those
+        if k < n {
+            unstable::select(s, n, k - 1, less);
+        }
+
+        dust::sort::<LinearInsertion, _, _>(s, usize::min(k, n), &SortOptions::DEFAULT, less);
+    }
+}
+
+#[inline(always)]
+fn sort_i32_common(v: &mut [i32]) -> bool {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    let opts = &SortOptions {
+        simd_i32: true,
+        ..SortOptions::DEFAULT
+    };
+
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    let opts = &SortOptions::DEFAULT;
+
+    sort_common::<LinearInsertion, _, _>(v, opts, &mut i32::lt)
+}
+
+// Sort `v` by `key`, an involution mapping the intended order of its elements onto that of `i32`.
+fn sort_by_i32_key(v: &mut [i32], key: impl Fn(i32) -> i32) -> bool {
+    v.iter_mut().for_each(|x| *x = key(*x));
+    let sorted = sort_i32_common(v);
+    v.iter_mut().for_each(|x| *x = key(*x));
+    sorted
+}
+
 #[inline(always)]
-pub fn sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) {
-    sort_common(v, &mut |x, y| f(x).lt(&f(y)));
+fn k_smallest_common<'a, T, F: FnMut(&T, &T) -> bool>(
+    v: &'a mut [T],
+    k: usize,
+    less: &mut F,
+) -> &'a mut [T] {
+    let (s, n) = (v.as_mut_ptr(), v.len());
+    let k = usize::min(k, n);
+    let opts = &SortOptions::DEFAULT;
+
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() > 0 && k > 0 {
+        unsafe {
+            // Filtering can't pay off when most elements are kept
+            if 2 * k >= n {
+                dust::sort::<LinearInsertion, _, _>(s, n, opts, less);
+                return &mut v[..k];
+            }
+
+            dust::sort::<LinearInsertion, _, _>(s, k, opts, less);
+
+            // Collect elements that beat the current `k`th smallest behind the front, in order, and
+            // merge them in whenever `k` have gathered. Elements in front always come from earlier
+            // in `v`, so ties stay stable.
+            let mut m = 0;
+
+            for i in k..n {
+                if less(&*s.add(i), &*s.add(k - 1)) {
+                    core::ptr::swap(s.add(k + m), s.add(i));
+                    m += 1;
+                }
+
+                if m == k || i == n - 1 && m > 0 {
+                    dust::sort::<LinearInsertion, _, _>(s, k + m, opts, less);
+                    m = 0;
+                }
+            }
+        }
+    }
+
+    &mut v[..k]
 }
 
 #[inline(always)]
-fn sort_common<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], less: &mut F) {
-    // Ignore ZSTs
+fn sort_split_common<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], b: &mut [T], less: &mut F) -> bool {
+    let opts = &SortOptions::DEFAULT;
+    let sorted_a = sort_common::<LinearInsertion, _, _>(a, opts, less);
+    let sorted_b = sort_common::<LinearInsertion, _, _>(b, opts, less);
+
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return true;
+    }
+
+    let (n1, n2) = (a.len(), b.len());
+    let (s1, s2) = (a.as_mut_ptr(), b.as_mut_ptr());
+
+    unsafe {
+        // Exchange the elements out of place across the boundary, leaving each side as two runs
+        // that merge independently
+        let rad = util::block_swap_length(s1, n1, s2, n2, less);
+
+        if rad == 0 {
+            return sorted_a && sorted_b;
+        }
+
+        core::ptr::swap_nonoverlapping(s1.add(n1 - rad), s2, rad);
+    }
+
+    sort_common::<LinearInsertion, _, _>(a, opts, less);
+    sort_common::<LinearInsertion, _, _>(b, opts, less);
+    false
+}
+
+#[inline(always)]
+fn merge_common<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], mid: usize, less: &mut F) {
+    assert!(mid <= v.len(), "mid > len");
+
+    // Ignore ZSTs; they can't be observably reordered
     if core::mem::size_of::<T>() == 0 {
         return;
     }
 
+    let (s, n) = (v.as_mut_ptr(), v.len());
+
     unsafe {
-        dust::sort(v.as_mut_ptr(), v.len(), less);
+        merge::merge_in_place(s, mid, n - mid, &SortOptions::DEFAULT, less);
+    }
+}
+
+fn merge_runs_common<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], boundaries: &[usize], less: &mut F) {
+    assert!(
+        boundaries.windows(2).all(|w| w[0] < w[1]),
+        "boundaries not strictly increasing"
+    );
+    assert!(
+        boundaries.last().is_none_or(|&b| b <= v.len()),
+        "boundary > len"
+    );
+
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return;
     }
+
+    let (s, n) = (v.as_mut_ptr(), v.len());
+
+    #[cfg(feature = "alloc")]
+    let mut alloc = Vec::<MaybeUninit<T>>::new();
+    #[cfg(feature = "alloc")]
+    let scratch = match alloc.try_reserve_exact(n / 2) {
+        Ok(()) => alloc.spare_capacity_mut(),
+        Err(_) => &mut [],
+    };
+
+    #[cfg(not(feature = "alloc"))]
+    let scratch: &mut [MaybeUninit<T>] = &mut [];
+
+    let (buf, cap) = (scratch.as_mut_ptr().cast(), scratch.len());
+    let opts = &SortOptions::DEFAULT;
+
+    // Run `i` of `k` starts at `start(i)`, and `start(k) == n`
+    let k = boundaries.len() + 1;
+    let start = |i: usize| match i {
+        0 => 0,
+        _ if i < k => boundaries[i - 1],
+        _ => n,
+    };
+
+    // Merge runs in pairs, then pairs of pairs and so on
+    let mut width = 1;
+
+    while width < k {
+        for i in (0..k - width).step_by(2 * width) {
+            let (l, m) = (start(i), start(i + width));
+            let r = start(usize::min(i + 2 * width, k));
+
+            unsafe {
+                merge::merge_scratch(s.add(l), m - l, r - m, buf, cap, opts, less);
+            }
+        }
+
+        width *= 2;
+    }
+}
+
+fn sort_count_inversions_common<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], less: &mut F) -> u64 {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return 0;
+    }
+
+    let (s, n) = (v.as_mut_ptr(), v.len());
+    let run = dust::MIN_RUN;
+    let mut count = 0u64;
+
+    // Each element passes the greater ones before it in its run
+    for l in (0..n).step_by(run) {
+        for i in l + 1..usize::min(l + run, n) {
+            unsafe {
+                let cur = s.add(i);
+                let shift = i - l - util::search_right(s.add(l), i - l, cur, less);
+                util::insert_left(cur, shift);
+                count = count.saturating_add(shift as u64);
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    let mut alloc = Vec::<MaybeUninit<T>>::new();
+    #[cfg(feature = "alloc")]
+    let scratch = match alloc.try_reserve_exact(n / 2) {
+        Ok(()) => alloc.spare_capacity_mut(),
+        Err(_) => &mut [],
+    };
+
+    #[cfg(not(feature = "alloc"))]
+    let scratch: &mut [MaybeUninit<T>] = &mut [];
+
+    let (buf, cap) = (scratch.as_mut_ptr().cast(), scratch.len());
+    let opts = &SortOptions::DEFAULT;
+    let mut width = run;
+
+    while width < n {
+        for l in (0..n - width).step_by(2 * width) {
+            let (m, r) = (l + width, usize::min(l + 2 * width, n));
+
+            unsafe {
+                let (s1, s2) = (s.add(l), s.add(m));
+
+                // Each element of the right run passes the greater ones of the left run
+                if less(&*s2, &*s2.sub(1)) {
+                    let mut i = 0;
+
+                    for j in 0..r - m {
+                        while i < width && !less(&*s2.add(j), &*s1.add(i)) {
+                            i += 1;
+                        }
+
+                        count = count.saturating_add((width - i) as u64);
+                    }
+                }
+
+                merge::merge_scratch(s1, width, r - m, buf, cap, opts, less);
+            }
+        }
+
+        width *= 2;
+    }
+
+    count
+}
+
+#[inline(always)]
+fn sort_dedup_common<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], less: &mut F) -> usize {
+    if v.is_empty() {
+        return 0;
+    }
+
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, less);
+
+    // Elements are sorted, so each one is either equal to the last distinct one or greater
+    let mut len = 1;
+
+    for i in 1..v.len() {
+        if less(&v[len - 1], &v[i]) {
+            v.swap(len, i);
+            len += 1;
+        }
+    }
+
+    len
+}
+
+#[inline(always)]
+fn sort_unstable_common<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], less: &mut F) -> bool {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return true;
+    }
+
+    unsafe { unstable::sort(v.as_mut_ptr(), v.len(), less) }
 }
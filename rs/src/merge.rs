@@ -3,15 +3,16 @@ use core::ptr;
 
 use crate::{
     buffer::Buffer,
-    dust::{MIN_FAST_LAZY, RATIO_BIN_MERGE},
+    options::SortOptions,
     util::{
-        advance, block_swap_length, conditional, cycle_swap, rotate, search_left, search_right,
-        Hole, Less,
+        advance, block_swap_length, conditional, count_moves, cycle_swap, gallop_lower_bound,
+        gallop_lower_bound_rev, ptr_sub, rotate, search_left, search_right, Hole, Less,
     },
 };
 
 /// Merge runs `s1..s1 + n1` and `s2..s2 + n2` into `dst..dst + n1 + n2` using a classic rightwards
-/// merge.
+/// merge. Each element displaced from the destination moves to the source slot just consumed, so
+/// `s1` may be a buffer anywhere outside the destination, even in a separate allocation.
 ///
 /// Return the number of elements merged in the loop.
 pub unsafe fn merge_right<T, F: Less<T>>(
@@ -105,7 +106,7 @@ pub unsafe fn exponential_merge_right<T, F: Less<T>>(
             d = d * 2 + 1;
         }
 
-        let mut r = i2 + (d + 1) / 2;
+        let mut r = i2 + d.div_ceil(2);
         r += search_left(s2.add(r), usize::min(i2 + d, n2) - r, s1.add(i1), less);
 
         while i2 < r {
@@ -170,6 +171,9 @@ pub unsafe fn exponential_merge_left<T, F: Less<T>>(
 
 /// Try to merge runs `s..s + n1` and `s + n1..s + n1 + n2` using an adaptive merge.
 ///
+/// `buf` only has to be disjoint from the runs, so it may lie on either side of them or in a
+/// separate allocation. It holds the same elements afterwards, though possibly out of order.
+///
 /// Return `false` if the merge could not be completed.
 #[inline(never)]
 pub unsafe fn merge<T, F: Less<T>>(
@@ -177,6 +181,7 @@ pub unsafe fn merge<T, F: Less<T>>(
     s: *mut T,
     n1: usize,
     n2: usize,
+    opts: &SortOptions,
     less: &mut F,
 ) -> bool {
     if n1 == 0 || n2 == 0 || !less(&*s.add(n1), &*s.add(n1 - 1)) {
@@ -195,20 +200,28 @@ pub unsafe fn merge<T, F: Less<T>>(
             return false;
         }
 
-        // Split case into two merges
+        // Split case into two merges. Neither splits again, whatever the comparator: each has a run
+        // of at most `max(n1, n2) - rad <= buf.len` elements, which bounds its own `rad`.
         ptr::swap_nonoverlapping(s.add(n1 - rad), s.add(n1), rad);
-        return merge(buf, s, n1 - rad, rad, less) && merge(buf, s.add(n1), rad, n2 - rad, less);
+        return merge(buf, s, n1 - rad, rad, opts, less)
+            && merge(buf, s.add(n1), rad, n2 - rad, opts, less);
+    }
+
+    // An empty merge means the comparator is inconsistent
+    if rad == 0 && opts.lenient {
+        merge_lazy(s, n1, n2, less);
+        return true;
     }
 
     buf.begin_merge(s.add(n1 - rad), rad);
 
-    if rad > (n1 - rad) / RATIO_BIN_MERGE {
+    if rad > (n1 - rad) / opts.ratio_bin_merge {
         merge_left(s, n1 - rad, s.add(n1), rad, s, less);
     } else {
         exponential_merge_left(s, n1 - rad, s.add(n1), rad, s, less);
     }
 
-    if rad > (n2 - rad) / RATIO_BIN_MERGE {
+    if rad > (n2 - rad) / opts.ratio_bin_merge {
         merge_right(buf.start, rad, s.add(n1 + rad), n2 - rad, s.add(n1), less);
     } else {
         exponential_merge_right(buf.start, rad, s.add(n1 + rad), n2 - rad, s.add(n1), less);
@@ -218,10 +231,14 @@ pub unsafe fn merge<T, F: Less<T>>(
 }
 
 /// Merge runs `s..s + n1` and `s + n1..s + n1 + n2` into `s..s + n1 + n2` with rotations.
+///
+/// Each step gallops from the end being merged, so it takes `O(log d)` comparisons to move a
+/// block of `d` elements into place.
 pub unsafe fn merge_lazy<T, F: Less<T>>(mut s: *mut T, mut n1: usize, mut n2: usize, less: &mut F) {
     if n2 <= n1 {
         while n2 > 0 {
-            let next_1 = search_right(s, n1, s.add(n1 + n2 - 1), less);
+            let last = s.add(n1 + n2 - 1);
+            let next_1 = gallop_lower_bound_rev(n1, |i| !less(&*last, &*s.add(i)));
 
             rotate(s.add(next_1), n1 - next_1, n2);
             n1 = next_1;
@@ -230,11 +247,13 @@ pub unsafe fn merge_lazy<T, F: Less<T>>(mut s: *mut T, mut n1: usize, mut n2: us
                 break;
             }
 
-            n2 = search_left(s.add(n1), n2 - 1, s.add(n1 - 1), less);
+            let (r, val) = (s.add(n1), s.add(n1 - 1));
+            n2 = gallop_lower_bound_rev(n2 - 1, |i| less(&*r.add(i), &*val));
         }
     } else {
         while n1 > 0 {
-            let r_adv = search_left(s.add(n1), n2, s, less);
+            let r = s.add(n1);
+            let r_adv = gallop_lower_bound(n2, |i| less(&*r.add(i), &*s));
 
             rotate(s, n1, r_adv);
             (s, n2) = advance(s, n2, r_adv);
@@ -243,7 +262,9 @@ pub unsafe fn merge_lazy<T, F: Less<T>>(mut s: *mut T, mut n1: usize, mut n2: us
                 break;
             }
 
-            (s, n1) = advance(s, n1, 1 + search_right(s.add(1), n1 - 1, s.add(n1), less));
+            let r = s.add(n1);
+            let l_adv = gallop_lower_bound(n1 - 1, |i| !less(&*r, &*s.add(i + 1)));
+            (s, n1) = advance(s, n1, 1 + l_adv);
         }
     }
 }
@@ -254,16 +275,21 @@ pub unsafe fn merge_in_place<T, F: Less<T>>(
     mut s: *mut T,
     mut n1: usize,
     mut n2: usize,
+    opts: &SortOptions,
     less: &mut F,
 ) {
     if n1 == 0 || n2 == 0 || !less(&*s.add(n1), &*s.add(n1 - 1)) {
         return;
     }
 
-    if n1 | n2 < MIN_FAST_LAZY {
+    if n1 | n2 < opts.min_fast_lazy {
         return merge_lazy(s, n1, n2, less);
     }
 
+    if less(&*s.add(n1 + n2 - 1), &*s) {
+        return rotate(s, n1, n2);
+    }
+
     // Trim right run; necessary for this algorithm to work on special sort
     n2 = search_left(s.add(n1), n2, s.add(n1 - 1), less);
 
@@ -305,3 +331,448 @@ pub unsafe fn merge_in_place<T, F: Less<T>>(
         }
     }
 }
+
+// A run moved out to scratch memory, which is copied back to `dst` on drop. This keeps the merged
+// region a permutation of its elements if the comparator panics.
+struct ScratchRun<T> {
+    start: *mut T,
+    end: *mut T,
+    dst: *mut T,
+}
+
+impl<T> Drop for ScratchRun<T> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::copy_nonoverlapping(self.start, self.dst, ptr_sub(self.end, self.start));
+        }
+    }
+}
+
+/// Merge runs `s..s + n1` and `s + n1..s + n1 + n2` by moving the shorter run out to `scratch`,
+/// which has room for `cap` elements. Fall back to [`merge_in_place`] if neither run fits.
+pub unsafe fn merge_scratch<T, F: Less<T>>(
+    s: *mut T,
+    n1: usize,
+    mut n2: usize,
+    scratch: *mut T,
+    cap: usize,
+    opts: &SortOptions,
+    less: &mut F,
+) {
+    if n1 == 0 || n2 == 0 || !less(&*s.add(n1), &*s.add(n1 - 1)) {
+        return;
+    }
+
+    // Trim elements that are already in place. Either run can only vanish if the comparator is
+    // inconsistent.
+    let (s, n1) = advance(s, n1, search_right(s, n1, s.add(n1), less));
+
+    if n1 == 0 {
+        return;
+    }
+
+    n2 = search_left(s.add(n1), n2, s.add(n1 - 1), less);
+
+    if n2 == 0 {
+        return;
+    }
+
+    if usize::min(n1, n2) > cap {
+        return merge_in_place(s, n1, n2, opts, less);
+    }
+
+    // Gallop through the longer run if the other is much shorter
+    if usize::min(n1, n2) <= usize::max(n1, n2) / opts.ratio_bin_merge {
+        return exponential_merge_scratch(s, n1, n2, scratch, less);
+    }
+
+    let q = usize::min(n1, n2) / opts.ratio_bin_merge;
+
+    // If at most `q` elements cross over, swap them and gallop through the two lopsided merges left
+    if !less(&*s.add(n1 + q), &*s.add(n1 - 1 - q)) {
+        let rad = block_swap_length(s.add(n1 - q), q, s.add(n1), q, less);
+        ptr::swap_nonoverlapping(s.add(n1 - rad), s.add(n1), rad);
+        exponential_merge_scratch(s, n1 - rad, rad, scratch, less);
+        return exponential_merge_scratch(s.add(n1), rad, n2 - rad, scratch, less);
+    }
+
+    if n1 <= n2 {
+        ptr::copy_nonoverlapping(s, scratch, n1);
+
+        let mut run = ScratchRun {
+            start: scratch,
+            end: scratch.add(n1),
+            dst: s,
+        };
+        let (mut r, end) = (s.add(n1), s.add(n1 + n2));
+
+        while run.start < run.end && r < end {
+            let is_r = less(&*r, &*run.start);
+            ptr::copy_nonoverlapping(conditional(run.start, r, is_r), run.dst, 1);
+
+            run.start = run.start.add(!is_r as usize);
+            r = r.add(is_r as usize);
+            run.dst = run.dst.add(1);
+        }
+
+        count_moves(n1 + ptr_sub(run.dst, s) + ptr_sub(run.end, run.start));
+    } else {
+        ptr::copy_nonoverlapping(s.add(n1), scratch, n2);
+
+        // Here `run.dst` marks the end of the unmerged part of the left run
+        let mut run = ScratchRun {
+            start: scratch,
+            end: scratch.add(n2),
+            dst: s.add(n1),
+        };
+        let mut dst_rev = s.add(n1 + n2);
+
+        while run.start < run.end && run.dst > s {
+            let is_l = less(&*run.end.sub(1), &*run.dst.sub(1));
+            dst_rev = dst_rev.sub(1);
+            ptr::copy_nonoverlapping(
+                conditional(run.end.sub(1), run.dst.sub(1), is_l),
+                dst_rev,
+                1,
+            );
+
+            run.end = run.end.sub(!is_l as usize);
+            run.dst = run.dst.sub(is_l as usize);
+        }
+
+        count_moves(n2 + ptr_sub(s.add(n1 + n2), dst_rev) + ptr_sub(run.end, run.start));
+    }
+}
+
+// Like the branchless merge in [`merge_scratch`], but gallop through the longer run and move it
+// in blocks. The shorter run has to fit in `scratch`.
+unsafe fn exponential_merge_scratch<T, F: Less<T>>(
+    s: *mut T,
+    n1: usize,
+    n2: usize,
+    scratch: *mut T,
+    less: &mut F,
+) {
+    if n1 == 0 || n2 == 0 {
+        return;
+    }
+
+    if n1 <= n2 {
+        ptr::copy_nonoverlapping(s, scratch, n1);
+
+        let mut run = ScratchRun {
+            start: scratch,
+            end: scratch.add(n1),
+            dst: s,
+        };
+        let (mut r, end) = (s.add(n1), s.add(n1 + n2));
+
+        while run.start < run.end {
+            let k = gallop_lower_bound(ptr_sub(end, r), |i| less(&*r.add(i), &*run.start));
+            ptr::copy(r, run.dst, k);
+            r = r.add(k);
+            run.dst = run.dst.add(k);
+
+            if r == end {
+                break;
+            }
+
+            ptr::copy_nonoverlapping(run.start, run.dst, 1);
+            run.start = run.start.add(1);
+            run.dst = run.dst.add(1);
+        }
+
+        count_moves(n1 + ptr_sub(run.dst, s) + ptr_sub(run.end, run.start));
+    } else {
+        ptr::copy_nonoverlapping(s.add(n1), scratch, n2);
+
+        // Here `run.dst` marks the end of the unmerged part of the left run
+        let mut run = ScratchRun {
+            start: scratch,
+            end: scratch.add(n2),
+            dst: s.add(n1),
+        };
+        let mut dst_rev = s.add(n1 + n2);
+
+        while run.start < run.end {
+            let last = run.end.sub(1);
+            let l = ptr_sub(run.dst, s);
+            let k = l - gallop_lower_bound_rev(l, |i| !less(&*last, &*s.add(i)));
+            run.dst = run.dst.sub(k);
+            dst_rev = dst_rev.sub(k);
+            ptr::copy(run.dst, dst_rev, k);
+
+            if run.dst == s {
+                break;
+            }
+
+            dst_rev = dst_rev.sub(1);
+            run.end = last;
+            ptr::copy_nonoverlapping(last, dst_rev, 1);
+        }
+
+        count_moves(n2 + ptr_sub(s.add(n1 + n2), dst_rev) + ptr_sub(run.end, run.start));
+    }
+}
+
+/// A merge pass from `src` to `dst`, one of which is the array being sorted and the other a buffer
+/// of the same length. Dropping this moves every element back into the array, so the array is a
+/// permutation of its original elements if the comparator panics.
+#[cfg(feature = "alloc")]
+pub struct PingPong<T> {
+    s: *mut T,
+    src: *mut T,
+    dst: *mut T,
+    n: usize,
+
+    // The output so far is `dst..dst + d`, and the current merge still has to consume
+    // `src + i..src + m` and `src + j..src + r`, followed by the untouched `src + r..src + n`
+    d: usize,
+    i: usize,
+    m: usize,
+    j: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Drop for PingPong<T> {
+    fn drop(&mut self) {
+        let (s, src, dst) = (self.s, self.src, self.dst);
+        let (d, i, m, j) = (self.d, self.i, self.m, self.j);
+
+        unsafe {
+            if src == s {
+                // Close the gap in the array left of `j`, then fill it with the output
+                ptr::copy(s.add(i), s.add(j - (m - i)), m - i);
+                ptr::copy_nonoverlapping(dst, s, d);
+            } else {
+                ptr::copy_nonoverlapping(src.add(i), s.add(d), m - i);
+                ptr::copy_nonoverlapping(src.add(j), s.add(j), self.n - j);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> PingPong<T> {
+    /// Begin a pass moving `s..s + n` from `src` to `dst`, one of which is `s`.
+    pub fn new(s: *mut T, src: *mut T, dst: *mut T, n: usize) -> Self {
+        let (d, i, m, j) = (0, 0, 0, 0);
+        Self {
+            s,
+            src,
+            dst,
+            n,
+            d,
+            i,
+            m,
+            j,
+        }
+    }
+
+    /// Merge the runs `src + d..src + m` and `src + m..src + r` into `dst + d..dst + r`, where `d` is
+    /// the end of the previous merge.
+    pub unsafe fn merge<F: Less<T>>(
+        &mut self,
+        m: usize,
+        r: usize,
+        opts: &SortOptions,
+        less: &mut F,
+    ) {
+        (self.m, self.j) = (m, m);
+        let src = self.src;
+
+        // Runs already in order are only copied
+        if self.i < m && m < r && less(&*src.add(m), &*src.add(m - 1)) {
+            let q = usize::min(m - self.i, r - m) / opts.ratio_bin_merge;
+
+            // If at most `q` elements cross over, merge them on their own so that the rest gallops
+            if !less(&*src.add(m + q), &*src.add(m - 1 - q)) {
+                let rad = block_swap_length(src.add(m - q), q, src.add(m), q, less);
+                self.merge_until(m - rad, m + rad, opts, less);
+            }
+
+            self.merge_until(m, r, opts, less);
+        }
+
+        ptr::copy_nonoverlapping(src.add(self.i), self.dst.add(self.d), m - self.i);
+        self.d += m - self.i;
+
+        ptr::copy_nonoverlapping(src.add(self.j), self.dst.add(self.d), r - self.j);
+        self.d += r - self.j;
+        (self.i, self.m, self.j) = (r, r, r);
+    }
+
+    // Merge `src + i..src + m_end` and `src + j..src + r_end` into `dst + d`, moving `i` and `j` to
+    // the ends. Gallop through the longer part when the other is `opts.ratio_bin_merge` times shorter.
+    #[inline(always)]
+    unsafe fn merge_until<F: Less<T>>(
+        &mut self,
+        m_end: usize,
+        r_end: usize,
+        opts: &SortOptions,
+        less: &mut F,
+    ) {
+        let (src, dst) = (self.src, self.dst);
+        let (n1, n2) = (m_end - self.i, r_end - self.j);
+
+        if usize::min(n1, n2) > usize::max(n1, n2) / opts.ratio_bin_merge {
+            while self.i < m_end && self.j < r_end {
+                let is_r = less(&*src.add(self.j), &*src.add(self.i));
+                let from = conditional(src.add(self.i), src.add(self.j), is_r);
+                ptr::copy_nonoverlapping(from, dst.add(self.d), 1);
+
+                self.i += !is_r as usize;
+                self.j += is_r as usize;
+                self.d += 1;
+            }
+        } else if n1 <= n2 {
+            while self.i < m_end {
+                let (l, r) = (src.add(self.i), src.add(self.j));
+                let k = gallop_lower_bound(r_end - self.j, |x| less(&*r.add(x), &*l));
+                ptr::copy_nonoverlapping(r, dst.add(self.d), k);
+                self.j += k;
+                self.d += k;
+
+                if self.j == r_end {
+                    break;
+                }
+
+                ptr::copy_nonoverlapping(l, dst.add(self.d), 1);
+                self.i += 1;
+                self.d += 1;
+            }
+        } else {
+            while self.j < r_end {
+                let (l, r) = (src.add(self.i), src.add(self.j));
+                let k = gallop_lower_bound(m_end - self.i, |x| !less(&*r, &*l.add(x)));
+                ptr::copy_nonoverlapping(l, dst.add(self.d), k);
+                self.i += k;
+                self.d += k;
+
+                if self.i == m_end {
+                    break;
+                }
+
+                ptr::copy_nonoverlapping(r, dst.add(self.d), 1);
+                self.j += 1;
+                self.d += 1;
+            }
+        }
+
+        ptr::copy_nonoverlapping(src.add(self.i), dst.add(self.d), m_end - self.i);
+        self.d += m_end - self.i;
+        self.i = m_end;
+
+        ptr::copy_nonoverlapping(src.add(self.j), dst.add(self.d), r_end - self.j);
+        self.d += r_end - self.j;
+        self.j = r_end;
+    }
+}
+
+/// Merge runs `s..s + n1` and `s + n1..s + n1 + n2` of `i32` in their natural order like
+/// [`merge_scratch`], but with SSE2 to merge four elements at a time.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+pub unsafe fn merge_scratch_i32(
+    s: *mut i32,
+    n1: usize,
+    mut n2: usize,
+    scratch: *mut i32,
+    cap: usize,
+    opts: &SortOptions,
+) {
+    use core::arch::x86_64::{_mm_loadu_si128, _mm_storeu_si128};
+
+    if n1 == 0 || n2 == 0 || *s.add(n1) >= *s.add(n1 - 1) {
+        return;
+    }
+
+    let (s, n1) = advance(s, n1, search_right(s, n1, s.add(n1), &mut i32::lt));
+    n2 = search_left(s.add(n1), n2, s.add(n1 - 1), &mut i32::lt);
+
+    if n1 < 4 || n2 < 4 || n1 > cap {
+        return merge_scratch(s, n1, n2, scratch, cap, opts, &mut i32::lt);
+    }
+
+    ptr::copy_nonoverlapping(s, scratch, n1);
+
+    let (mut a, a_end) = (scratch.add(4), scratch.add(n1));
+    let (mut b, b_end) = (s.add(n1 + 4), s.add(n1 + n2));
+    let mut dst = s;
+
+    // Carry the upper half of each merged pair into the next, refilling from the run with the
+    // smaller head. Stores stay behind the unread part of the right run.
+    let mut va = _mm_loadu_si128(scratch.cast());
+    let mut vb = _mm_loadu_si128(s.add(n1).cast());
+
+    loop {
+        let (lo, hi) = bitonic_merge_4(va, vb);
+        _mm_storeu_si128(dst.cast(), lo);
+        dst = dst.add(4);
+        vb = hi;
+
+        let take_a = b == b_end || a < a_end && *a <= *b;
+        let (src, end) = conditional((b, b_end), (a, a_end), take_a);
+
+        if ptr_sub(end, src) < 4 {
+            break;
+        }
+
+        va = _mm_loadu_si128(src.cast());
+        a = a.add(4 * take_a as usize);
+        b = b.add(4 * !take_a as usize);
+    }
+
+    // Merge the carried elements and the rest of the left run, leaving the rest of the right run
+    // in place
+    let mut h = [0; 4];
+    _mm_storeu_si128(h.as_mut_ptr().cast(), vb);
+    let mut i = 0;
+
+    while i < 4 || a < a_end {
+        let from_h = a == a_end || i < 4 && h[i] <= *a;
+        let x = if from_h { h[i] } else { *a };
+
+        if b < b_end && *b < x {
+            dst.write(*b);
+            b = b.add(1);
+        } else {
+            dst.write(x);
+            i += from_h as usize;
+            a = a.add(!from_h as usize);
+        }
+
+        dst = dst.add(1);
+    }
+
+    count_moves(2 * n1 + n2);
+}
+
+// Merge the sorted vectors `a` and `b`, returning the lower and upper halves in order.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[inline(always)]
+unsafe fn bitonic_merge_4(
+    a: core::arch::x86_64::__m128i,
+    b: core::arch::x86_64::__m128i,
+) -> (core::arch::x86_64::__m128i, core::arch::x86_64::__m128i) {
+    use core::arch::x86_64::*;
+
+    // Lanewise minimum and maximum
+    let minmax = |a, b| {
+        let gt = _mm_cmpgt_epi32(a, b);
+        let min = _mm_or_si128(_mm_and_si128(gt, b), _mm_andnot_si128(gt, a));
+        let max = _mm_or_si128(_mm_and_si128(gt, a), _mm_andnot_si128(gt, b));
+        (min, max)
+    };
+
+    // Sort a bitonic vector with comparisons at distance 2, then 1
+    let sort_bitonic = |x| {
+        let (min, max) = minmax(x, _mm_shuffle_epi32::<0b01_00_11_10>(x));
+        let x = _mm_unpacklo_epi64(min, max);
+        let (min, max) = minmax(x, _mm_shuffle_epi32::<0b10_11_00_01>(x));
+        _mm_unpacklo_epi64(_mm_unpacklo_epi32(min, max), _mm_unpackhi_epi32(min, max))
+    };
+
+    // Against `b` reversed, the lower and upper halves are each bitonic
+    let (lo, hi) = minmax(a, _mm_shuffle_epi32::<0b00_01_10_11>(b));
+    (sort_bitonic(lo), sort_bitonic(hi))
+}
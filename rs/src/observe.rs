@@ -0,0 +1,51 @@
+use crate::{dust, options::SortOptions, small_sort::LinearInsertion};
+
+/// A decision made by the stable sort, reported by [`sort_with_observer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// `len` more elements were sorted into runs, either as the natural run at the start or by
+    /// sorting a short run and extending it with the natural run that follows.
+    RunBuilt {
+        /// The number of elements added to runs.
+        len: usize,
+    },
+
+    /// `count` distinct keys were collected from the input to serve as an internal buffer.
+    KeysCollected {
+        /// The number of keys collected.
+        count: usize,
+    },
+
+    /// Block merging started with blocks of `block_len` elements.
+    BlockMergeStarted {
+        /// The initial block length.
+        block_len: usize,
+    },
+
+    /// The sort fell back to rotation-based merging, because the input had too few distinct
+    /// elements or only a short unsorted tail.
+    FellBackToSpecial,
+}
+
+// Observer that discards every event, for sorts that aren't observed
+#[inline(always)]
+pub fn ignore(_: Event) {}
+
+/// Sort `v` with a strict "less than" predicate `less`, calling `obs` with each [`Event`] along the
+/// way. This shows which path the sort takes through a given input.
+///
+/// Return `true` if `v` was already sorted with respect to `less`, in which case no events are
+/// reported.
+pub fn sort_with_observer<T, F: FnMut(&T, &T) -> bool, O: FnMut(Event)>(
+    v: &mut [T],
+    mut less: F,
+    mut obs: O,
+) -> bool {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 {
+        return true;
+    }
+
+    let (s, n, opts) = (v.as_mut_ptr(), v.len(), &SortOptions::DEFAULT);
+    unsafe { dust::sort_observed::<LinearInsertion, _, _, _>(s, n, opts, &mut less, &mut obs) }
+}
@@ -0,0 +1,97 @@
+use crate::dust::{MIN_DISTINCT, MIN_FAST_LAZY, MIN_MERGE_SORT, MIN_RUN, RATIO_BIN_MERGE};
+
+/// Tuning thresholds for the stable sorts. The defaults are the values used by [`sort`] and its
+/// variants.
+///
+/// [`sort`]: crate::sort
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortOptions {
+    pub(crate) min_run: usize,
+    pub(crate) min_fast_lazy: usize,
+    pub(crate) ratio_bin_merge: usize,
+    pub(crate) min_merge_sort: usize,
+    pub(crate) min_distinct: usize,
+
+    pub(crate) lenient: bool,
+
+    // Elements are `i32` in their natural order, so merges may be vectorized
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    pub(crate) simd_i32: bool,
+}
+
+impl SortOptions {
+    /// The default thresholds.
+    pub const DEFAULT: Self = Self {
+        min_run: MIN_RUN,
+        min_fast_lazy: MIN_FAST_LAZY,
+        ratio_bin_merge: RATIO_BIN_MERGE,
+        min_merge_sort: MIN_MERGE_SORT,
+        min_distinct: MIN_DISTINCT,
+        lenient: false,
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        simd_i32: false,
+    };
+
+    /// Create options with the default thresholds.
+    pub const fn new() -> Self {
+        Self::DEFAULT
+    }
+
+    /// Create runs of this size at the small-sort level.
+    ///
+    /// Panics if `min_run` is not a power of two.
+    pub const fn min_run(mut self, min_run: usize) -> Self {
+        assert!(min_run.is_power_of_two(), "min_run is not a power of two");
+        self.min_run = min_run;
+        self
+    }
+
+    /// For two runs of size `n1, n2` where `n1 | n2 < min_fast_lazy`, prefer simple lazy stable
+    /// merging over the advanced version.
+    pub const fn min_fast_lazy(mut self, min_fast_lazy: usize) -> Self {
+        self.min_fast_lazy = min_fast_lazy;
+        self
+    }
+
+    /// For two runs of size `n1, n2` where the smaller run size `n1` satisfies
+    /// `n1 <= n2 / ratio_bin_merge`, prefer binary merging over branchless merging.
+    ///
+    /// The best value depends on the cost of the comparator rather than the size of the elements.
+    /// Lowering it to 2 or 4 saves comparisons on unbalanced merges, which pays off for expensive
+    /// comparators such as string comparisons.
+    ///
+    /// Panics if `ratio_bin_merge == 0`.
+    pub const fn ratio_bin_merge(mut self, ratio_bin_merge: usize) -> Self {
+        assert!(ratio_bin_merge > 0, "ratio_bin_merge == 0");
+        self.ratio_bin_merge = ratio_bin_merge;
+        self
+    }
+
+    /// Avoid the overhead of block merge sort on arrays smaller than this.
+    pub const fn min_merge_sort(mut self, min_merge_sort: usize) -> Self {
+        self.min_merge_sort = min_merge_sort;
+        self
+    }
+
+    /// Use a special strategy on arrays with less than this many comparatively unequal elements.
+    ///
+    /// Panics if `min_distinct == 0`.
+    pub const fn min_distinct(mut self, min_distinct: usize) -> Self {
+        assert!(min_distinct > 0, "min_distinct == 0");
+        self.min_distinct = min_distinct;
+        self
+    }
+
+    /// If `lenient`, don't panic when the comparator is found inconsistent, and instead fall back to
+    /// rotation-based merging. The sort always terminates and leaves some permutation of the input.
+    pub const fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
@@ -0,0 +1,120 @@
+use core::mem::MaybeUninit;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{
+    merge, sort,
+    util::{lower_bound, rotate},
+    SortOptions,
+};
+
+// Sort and merge regions up to this length on the current task
+const MAX_SEQUENTIAL: usize = 1 << 13;
+
+/// A way to run two closures, possibly in parallel, for [`par_sort`]. For example, this can be
+/// implemented with `rayon::join` or with scoped threads.
+pub trait Spawn: Sync {
+    /// Run `a` and `b`, possibly in parallel, returning once both have finished.
+    fn join<A: FnOnce() + Send, B: FnOnce() + Send>(&self, a: A, b: B);
+}
+
+/// Stably sort `v` like [`sort`], splitting the work into tasks run by `spawn`.
+///
+/// The halves of `v` are sorted in parallel and then merged, and each merge is split into
+/// independent halves in turn, down to regions of 8192 elements. With the `alloc` feature, the
+/// merges share one buffer of half the length of `v`, allocated up front and divided between the
+/// tasks. Otherwise, or if it can't be allocated, they merge in place.
+pub fn par_sort<T: Ord + Send, S: Spawn>(v: &mut [T], spawn: &S) {
+    // Ignore ZSTs; they can't be observably reordered
+    if core::mem::size_of::<T>() == 0 || v.len() <= MAX_SEQUENTIAL {
+        sort(v);
+        return;
+    }
+
+    #[cfg(feature = "alloc")]
+    let mut alloc = Vec::<T>::new();
+    #[cfg(feature = "alloc")]
+    let scratch = match alloc.try_reserve_exact(v.len() / 2) {
+        Ok(()) => alloc.spare_capacity_mut(),
+        Err(_) => &mut [],
+    };
+
+    #[cfg(not(feature = "alloc"))]
+    let scratch: &mut [MaybeUninit<T>] = &mut [];
+
+    par_sort_scratch(v, scratch, spawn);
+}
+
+// Sort `v` like `par_sort`, merging through `scratch`, which is either empty or at least half the
+// length of `v`.
+fn par_sort_scratch<T: Ord + Send, S: Spawn>(
+    v: &mut [T],
+    scratch: &mut [MaybeUninit<T>],
+    spawn: &S,
+) {
+    if v.len() <= MAX_SEQUENTIAL {
+        sort(v);
+        return;
+    }
+
+    let mid = v.len() / 2;
+    let (l, r) = v.split_at_mut(mid);
+    let (sl, sr) = split_scratch(scratch, mid);
+    spawn.join(
+        || par_sort_scratch(l, sl, spawn),
+        || par_sort_scratch(r, sr, spawn),
+    );
+
+    par_merge(v, mid, scratch, spawn);
+}
+
+// Split `scratch` so that each side of `v` split at `mid` gets at least half its length, unless
+// `scratch` is empty.
+fn split_scratch<T>(
+    scratch: &mut [MaybeUninit<T>],
+    mid: usize,
+) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+    scratch.split_at_mut(usize::min(mid / 2, scratch.len()))
+}
+
+// Merge the sorted regions `v[..mid]` and `v[mid..]` through `scratch`, splitting the work into
+// tasks run by `spawn`.
+fn par_merge<T: Ord + Send, S: Spawn>(
+    v: &mut [T],
+    mid: usize,
+    scratch: &mut [MaybeUninit<T>],
+    spawn: &S,
+) {
+    let n = v.len();
+
+    if n <= MAX_SEQUENTIAL || mid == 0 || mid == n {
+        let (buf, cap) = (scratch.as_mut_ptr().cast(), scratch.len());
+        let opts = &SortOptions::DEFAULT;
+
+        unsafe { merge::merge_scratch(v.as_mut_ptr(), mid, n - mid, buf, cap, opts, &mut T::lt) }
+        return;
+    }
+
+    // Split the longer run in the middle and the shorter run where that element belongs. Equal
+    // elements of the right run stay after those of the left run.
+    let (i, j) = if mid >= n - mid {
+        let i = mid / 2;
+        (i, mid + lower_bound(n - mid, |k| v[mid + k] < v[i]))
+    } else {
+        let j = mid + (n - mid) / 2;
+        (lower_bound(mid, |k| v[k] <= v[j]), j)
+    };
+
+    // Swap the inner parts so that the two halves can be merged independently
+    unsafe {
+        rotate(v.as_mut_ptr().add(i), mid - i, j - mid);
+    }
+
+    let (l, r) = v.split_at_mut(i + j - mid);
+    let (sl, sr) = split_scratch(scratch, l.len());
+    spawn.join(
+        || par_merge(l, i, sl, spawn),
+        || par_merge(r, mid - i, sr, spawn),
+    );
+}
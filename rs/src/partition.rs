@@ -0,0 +1,39 @@
+use crate::util::{insert_left, rotate};
+
+// Partition regions up to this length by insertion
+const MAX_INSERT_PARTITION: usize = 16;
+
+/// Move the elements of `s..s + n` satisfying `pred` to the front, keeping the relative order of
+/// the elements on both sides. `pred` is called once per element.
+///
+/// Return the number of elements satisfying `pred`.
+pub unsafe fn stable_partition<T, F: FnMut(&T) -> bool>(
+    s: *mut T,
+    n: usize,
+    pred: &mut F,
+) -> usize {
+    if n <= MAX_INSERT_PARTITION {
+        let mut k = 0;
+
+        for i in 0..n {
+            if pred(&*s.add(i)) {
+                if i > k {
+                    insert_left(s.add(i), i - k);
+                }
+
+                k += 1;
+            }
+        }
+
+        return k;
+    }
+
+    // Partition both halves, then swap the failing elements of the left half with the passing
+    // elements of the right half
+    let h = n / 2;
+    let l = stable_partition(s, h, pred);
+    let r = stable_partition(s.add(h), n - h, pred);
+
+    rotate(s.add(l), h - l, r);
+    l + r
+}
@@ -0,0 +1,293 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{
+    is_sorted, sort_by, sort_by_key,
+    util::{insert_left, lower_bound, rotate},
+};
+
+// Sort runs up to this length by binary insertion in `sort_by_precomputed_key`
+const MAX_INSERT_PAIRS: usize = 16;
+
+/// Rearrange `v` so that the element at index `i` is the one previously at index `perm[i]`, by
+/// following the cycles of `perm`. Afterwards, `perm` is the identity permutation.
+///
+/// Panics if `perm` is not a permutation of `0..v.len()`.
+pub fn apply_permutation<T>(v: &mut [T], perm: &mut [u32]) {
+    assert_eq!(
+        v.len(),
+        perm.len(),
+        "permutation length must match slice length"
+    );
+    assert!(v.len() <= u32::MAX as usize + 1);
+
+    for i in 0..v.len() {
+        let mut j = i;
+
+        loop {
+            let k = perm[j] as usize;
+            perm[j] = j as u32;
+
+            if k == i {
+                break;
+            }
+
+            // A fixed point other than the start means `k` has two preimages
+            assert_ne!(perm[k] as usize, k, "not a permutation");
+            v.swap(j, k);
+            j = k;
+        }
+    }
+}
+
+/// Return the indices of `v` in the order that stably sorts it, so that `v[result[i]]` is
+/// non-descending and equal elements keep their original order.
+///
+/// Panics if `v.len() > u32::MAX + 1`; see [`argsort_usize`].
+#[cfg(feature = "alloc")]
+pub fn argsort<T: Ord>(v: &[T]) -> Vec<u32> {
+    let mut order = alloc::vec![0; v.len()];
+    sort_indices_into(v, &mut order);
+    order
+}
+
+/// Return the indices of `v` in the order that stably sorts it, like [`argsort`] but without a
+/// limit on the length of `v`.
+#[cfg(feature = "alloc")]
+pub fn argsort_usize<T: Ord>(v: &[T]) -> Vec<usize> {
+    let mut order = alloc::vec![0; v.len()];
+    sort_indices_into_usize(v, &mut order);
+    order
+}
+
+/// Fill `out` with the indices of `v` in the order that stably sorts it, like `argsort` but
+/// without allocating.
+///
+/// Panics if `out.len() != v.len()` or `v.len() > u32::MAX + 1`; see [`sort_indices_into_usize`].
+pub fn sort_indices_into<T: Ord>(v: &[T], out: &mut [u32]) {
+    assert_eq!(v.len(), out.len(), "output length must match slice length");
+    assert!(
+        v.len() <= u32::MAX as usize + 1,
+        "too many elements for u32 indices"
+    );
+
+    out.iter_mut().enumerate().for_each(|(i, x)| *x = i as u32);
+    sort_by(out, |&i, &j| v[i as usize].cmp(&v[j as usize]));
+}
+
+/// Fill `out` with the indices of `v` in the order that stably sorts it, like
+/// [`sort_indices_into`] but without a limit on the length of `v`.
+///
+/// Panics if `out.len() != v.len()`.
+pub fn sort_indices_into_usize<T: Ord>(v: &[T], out: &mut [usize]) {
+    assert_eq!(v.len(), out.len(), "output length must match slice length");
+
+    out.iter_mut().enumerate().for_each(|(i, x)| *x = i);
+    sort_by(out, |&i, &j| v[i].cmp(&v[j]));
+}
+
+/// Sort `v` and return the permutation that restores its original order with
+/// [`apply_permutation`].
+///
+/// Panics if `v.len() > u32::MAX + 1`.
+#[cfg(feature = "alloc")]
+pub fn sort_with_undo<T: Ord>(v: &mut [T]) -> Vec<u32> {
+    let mut order = argsort(v);
+    let mut undo = alloc::vec![0; v.len()];
+
+    for (i, &j) in order.iter().enumerate() {
+        undo[j as usize] = i as u32;
+    }
+
+    apply_permutation(v, &mut order);
+    undo
+}
+
+/// Sort `v` with a key extraction function `f`, calling `f` only once per element.
+///
+/// With the `alloc` feature, the keys are sorted alongside their indices and the resulting
+/// permutation is applied to `v` in-place. Otherwise, or if `v.len() > u32::MAX`, this falls back to
+/// [`sort_by_key`].
+///
+/// Return `true` if `v` was already sorted with respect to `f`.
+pub fn sort_by_cached_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], f: F) -> bool {
+    #[cfg(feature = "alloc")]
+    if v.len() <= u32::MAX as usize {
+        let mut keys = v.iter().map(f).zip(0..).collect::<Vec<(K, u32)>>();
+
+        if sort_by(&mut keys, |x, y| x.0.cmp(&y.0)) {
+            return true;
+        }
+
+        let mut order = keys.into_iter().map(|(_, i)| i).collect::<Vec<_>>();
+        apply_permutation(v, &mut order);
+
+        return false;
+    }
+
+    sort_by_key(v, f)
+}
+
+/// Sort `v` with a key extraction function `f` like [`sort_by_cached_key`], calling `f` only once
+/// per element, and return the keys in sorted order, so that the key of `v[i]` is at index `i`.
+///
+/// If `v.len() > u32::MAX`, this falls back to [`sort_by_precomputed_key`].
+#[cfg(feature = "alloc")]
+pub fn sort_by_key_collect<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], f: F) -> Vec<K> {
+    if v.len() > u32::MAX as usize {
+        let mut keys = v.iter().map(f).collect::<Vec<_>>();
+        sort_by_precomputed_key(v, &mut keys);
+        return keys;
+    }
+
+    let mut keys = v.iter().map(f).zip(0..).collect::<Vec<(K, u32)>>();
+
+    if !sort_by(&mut keys, |x, y| x.0.cmp(&y.0)) {
+        let mut order = keys.iter().map(|&(_, i)| i).collect::<Vec<_>>();
+        apply_permutation(v, &mut order);
+    }
+
+    keys.into_iter().map(|(k, _)| k).collect()
+}
+
+/// Sort `v` by a categorical rank `rank` no greater than `max_rank`, calling `rank` only once per
+/// element.
+///
+/// For `max_rank <= v.len()`, this counts the elements of each rank to find the stable order in
+/// `O(n + max_rank)` time. Otherwise it sorts the ranks with their indices like
+/// [`sort_by_cached_key`]. Either way, the order is then applied to `v` in-place. If
+/// `v.len() > u32::MAX`, this falls back to [`sort_by_key`].
+///
+/// Panics if `rank` returns a value greater than `max_rank`.
+///
+/// Return `true` if `v` was already sorted by rank, in which case it is left untouched.
+#[cfg(feature = "alloc")]
+pub fn sort_by_small_rank<T, F: FnMut(&T) -> u32>(v: &mut [T], mut rank: F, max_rank: u32) -> bool {
+    let n = v.len();
+
+    if n > u32::MAX as usize {
+        return sort_by_key(v, |x| {
+            let r = rank(x);
+            assert!(r <= max_rank, "rank > max_rank");
+            r
+        });
+    }
+
+    let ranks = v.iter().map(&mut rank).collect::<Vec<_>>();
+    assert!(ranks.iter().all(|&r| r <= max_rank), "rank > max_rank");
+
+    if is_sorted(&ranks) {
+        return true;
+    }
+
+    let mut order = if max_rank as usize <= n {
+        // Find where each rank starts in the sorted order, then place indices in order
+        let mut start = alloc::vec![0u32; max_rank as usize + 1];
+
+        for &r in &ranks {
+            start[r as usize] += 1;
+        }
+
+        let mut sum = 0;
+
+        for cnt in &mut start {
+            (*cnt, sum) = (sum, sum + *cnt);
+        }
+
+        let mut order = alloc::vec![0u32; n];
+
+        for (i, &r) in (0..).zip(&ranks) {
+            order[start[r as usize] as usize] = i;
+            start[r as usize] += 1;
+        }
+
+        order
+    } else {
+        let mut keys = ranks.into_iter().zip(0..).collect::<Vec<(u32, u32)>>();
+        sort_by(&mut keys, |x, y| x.0.cmp(&y.0));
+        keys.into_iter().map(|(_, i)| i).collect()
+    };
+
+    apply_permutation(v, &mut order);
+    false
+}
+
+/// Sort `v` by the keys in `keys`, where `keys[i]` is the key of `v[i]`, rearranging `keys` the same
+/// way so that they stay in correspondence. Comparisons only read `keys`, and nothing is allocated.
+///
+/// Elements are merged with rotations, so this takes `O(n log n)` comparisons but `O(n log^2 n)`
+/// moves. It only pays off over [`sort_by_key`] when keys are expensive to compute.
+///
+/// Panics if `keys.len() != v.len()`.
+///
+/// Return `true` if `keys` was already sorted, in which case both slices are left untouched.
+pub fn sort_by_precomputed_key<T, K: Ord>(v: &mut [T], keys: &mut [K]) -> bool {
+    assert_eq!(v.len(), keys.len(), "key length must match slice length");
+
+    if is_sorted(keys) {
+        return true;
+    }
+
+    let n = v.len();
+
+    for l in (0..n).step_by(MAX_INSERT_PAIRS) {
+        let r = usize::min(l + MAX_INSERT_PAIRS, n);
+        insert_sort_pairs(&mut v[l..r], &mut keys[l..r]);
+    }
+
+    let mut run = MAX_INSERT_PAIRS;
+
+    while run < n {
+        for l in (0..n - run).step_by(2 * run) {
+            let r = usize::min(l + 2 * run, n);
+            merge_pairs(&mut v[l..r], &mut keys[l..r], run);
+        }
+
+        run *= 2;
+    }
+
+    false
+}
+
+// Sort `v` and `keys` by `keys` with binary insertion, moving both in the same way.
+fn insert_sort_pairs<T, K: Ord>(v: &mut [T], keys: &mut [K]) {
+    for i in 1..keys.len() {
+        // Insert after equal keys to keep the sort stable
+        let j = lower_bound(i, |j| keys[j] <= keys[i]);
+
+        unsafe {
+            insert_left(v.as_mut_ptr().add(i), i - j);
+            insert_left(keys.as_mut_ptr().add(i), i - j);
+        }
+    }
+}
+
+// Merge the sorted regions `..mid` and `mid..` of `v` and `keys` by `keys`, moving both in the same
+// way.
+fn merge_pairs<T, K: Ord>(v: &mut [T], keys: &mut [K], mid: usize) {
+    let n = keys.len();
+
+    if mid == 0 || mid == n || keys[mid - 1] <= keys[mid] {
+        return;
+    }
+
+    // Split the longer run in the middle and the shorter run where that key belongs. Equal keys of
+    // the right run stay after those of the left run.
+    let (i, j) = if mid >= n - mid {
+        let i = mid / 2;
+        (i, mid + lower_bound(n - mid, |k| keys[mid + k] < keys[i]))
+    } else {
+        let j = mid + (n - mid) / 2;
+        (lower_bound(mid, |k| keys[k] <= keys[j]), j)
+    };
+
+    unsafe {
+        rotate(v.as_mut_ptr().add(i), mid - i, j - mid);
+        rotate(keys.as_mut_ptr().add(i), mid - i, j - mid);
+    }
+
+    let m = i + j - mid;
+    let ((v1, v2), (k1, k2)) = (v.split_at_mut(m), keys.split_at_mut(m));
+    merge_pairs(v1, k1, i);
+    merge_pairs(v2, k2, mid - i);
+}
@@ -0,0 +1,3 @@
+//! Low-level building blocks of the sorts, for moving elements of your own.
+
+pub use crate::util::Hole;
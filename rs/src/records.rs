@@ -0,0 +1,96 @@
+use core::slice;
+
+use crate::{
+    dust::MIN_RUN,
+    util::{lower_bound, rotate},
+};
+
+// A region of fixed-size byte records, ordered by their leading key bytes
+struct Records {
+    // Pointer to the first byte of the first record
+    s: *mut u8,
+
+    // Number of bytes in each record
+    record_len: usize,
+
+    // Number of leading bytes in each record that make up its key
+    key_len: usize,
+}
+
+impl Records {
+    // Return whether the key of record `i` is lexicographically less than the key of record `j`.
+    unsafe fn less(&self, i: usize, j: usize) -> bool {
+        let key = |i: usize| slice::from_raw_parts(self.s.add(i * self.record_len), self.key_len);
+        key(i) < key(j)
+    }
+
+    // Exchange the records `l..m` and `m..r` in-place.
+    unsafe fn rotate(&self, l: usize, m: usize, r: usize) {
+        let len = self.record_len;
+        rotate(self.s.add(l * len), (m - l) * len, (r - m) * len);
+    }
+
+    // Sort records `l..r` with binary insertion sort.
+    unsafe fn insert_sort(&self, l: usize, r: usize) {
+        for i in l + 1..r {
+            let pos = l + lower_bound(i - l, |x| !self.less(i, l + x));
+            self.rotate(pos, i, i + 1);
+        }
+    }
+
+    // Merge the records `a..m` and `m..b` with the rotation-based SymMerge algorithm by Kim and
+    // Kutzner. The recursion depth is `O(log(b - a))`.
+    unsafe fn merge(&self, a: usize, m: usize, b: usize) {
+        if a == m || m == b {
+            return;
+        }
+
+        if m - a == 1 {
+            let i = m + lower_bound(b - m, |x| self.less(m + x, a));
+            return self.rotate(a, m, i);
+        }
+
+        if b - m == 1 {
+            let i = a + lower_bound(m - a, |x| !self.less(m, a + x));
+            return self.rotate(i, m, b);
+        }
+
+        let mid = a + (b - a) / 2;
+        let n = mid + m;
+        let (lo, hi) = if m > mid { (n - b, mid) } else { (a, m) };
+
+        let start = lo + lower_bound(hi - lo, |x| !self.less(n - 1 - (lo + x), lo + x));
+        let end = n - start;
+
+        if start < m && m < end {
+            self.rotate(start, m, end);
+        }
+
+        self.merge(a, start, mid);
+        self.merge(mid, end, b);
+    }
+}
+
+/// Stably sort the `n` records of length `record_len` starting at `s` by their leading `key_len`
+/// bytes.
+pub unsafe fn sort(s: *mut u8, n: usize, record_len: usize, key_len: usize) {
+    let records = Records {
+        s,
+        record_len,
+        key_len,
+    };
+
+    for l in (0..n).step_by(MIN_RUN) {
+        records.insert_sort(l, usize::min(l + MIN_RUN, n));
+    }
+
+    let mut run = MIN_RUN;
+
+    while run < n {
+        for l in (0..n - run).step_by(2 * run) {
+            records.merge(l, l + run, usize::min(l + 2 * run, n));
+        }
+
+        run *= 2;
+    }
+}
@@ -1,22 +1,47 @@
 use crate::{
-    dust::{insert_sort, MIN_RUN},
+    observe::Event,
+    options::SortOptions,
+    small_sort::SmallSort,
     util::{advance, ptr_sub, reverse, Less},
 };
 
 /// Return the length of the longest non-descending prefix of `s..s + n`.
-pub unsafe fn next_non_desc_run<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) -> usize {
+pub unsafe fn next_non_desc_run<T, F: Less<T>>(s: *const T, n: usize, less: &mut F) -> usize {
     (1..n)
         .find(|&i| less(&*s.add(i), &*s.add(i - 1)))
         .unwrap_or(n)
 }
 
+/// Return whether `cnt` evenly spaced elements of `s..s + n` are in non-descending order, assuming
+/// `0 < cnt <= n`.
+pub unsafe fn sampled_non_desc<T, F: Less<T>>(
+    s: *const T,
+    n: usize,
+    cnt: usize,
+    less: &mut F,
+) -> bool {
+    let step = n / cnt;
+    (1..cnt).all(|i| !less(&*s.add(i * step), &*s.add((i - 1) * step)))
+}
+
 /// Construct the next longest run starting at `s` with max length `n`.
 ///
 /// Return the length of the run.
 pub unsafe fn next_sorted_run<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) -> usize {
     // Scan for initial non-descending run
-    let mut i = next_non_desc_run(s, n, less);
+    extend_sorted_run(s, next_non_desc_run(s, n, less), n, less)
+}
 
+/// Construct the next longest run starting at `s` with max length `n`, given that the longest
+/// non-descending prefix of `s..s + n` has length `i`.
+///
+/// Return the length of the run.
+pub unsafe fn extend_sorted_run<T, F: Less<T>>(
+    s: *mut T,
+    mut i: usize,
+    n: usize,
+    less: &mut F,
+) -> usize {
     if i == n || i > 1 && less(&*s, &*s.add(i - 1)) {
         return i;
     }
@@ -26,16 +51,22 @@ pub unsafe fn next_sorted_run<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F)
 
     // Flip equal segments until we reach an ascending pair
     loop {
-        i += 1;
+        // Descending pairs only start new segments, so scan them in a tight loop. A fully
+        // descending run takes one comparison per element and no flips until the end.
+        let j = i;
 
-        if i == n {
-            break;
+        while i + 1 < n && less(&*s.add(i + 1), &*s.add(i)) {
+            i += 1;
         }
 
-        if less(&*s.add(i), &*s.add(i - 1)) {
-            reverse(l, s.add(i));
+        if i > j {
+            reverse(l, s.add(j + 1));
             l = s.add(i);
-        } else if less(&*s.add(i - 1), &*s.add(i)) {
+        }
+
+        i += 1;
+
+        if i == n || less(&*s.add(i - 1), &*s.add(i)) {
             break;
         }
     }
@@ -45,19 +76,39 @@ pub unsafe fn next_sorted_run<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F)
     i
 }
 
-/// Build runs of the minimum starting length on `s..s + n` assuming the first `i` elements are done
-/// already. Only the last/rightmost run may be less than the minimum length.
-pub unsafe fn build_runs<T, F: Less<T>>(mut s: *mut T, mut i: *mut T, mut n: usize, less: &mut F) {
+/// Build runs of the minimum starting length `opts.min_run` on `s..s + n` assuming the first `i`
+/// elements are done already. Only the last/rightmost run may be less than the minimum length.
+/// Runs are sorted with the strategy `S`, and each is reported to `obs`.
+pub unsafe fn build_runs<S: SmallSort, T, F: Less<T>, O: FnMut(Event)>(
+    mut s: *mut T,
+    mut i: *mut T,
+    mut n: usize,
+    opts: &SortOptions,
+    less: &mut F,
+    obs: &mut O,
+) {
+    // Don't form a pointer past an empty region
+    if n == 0 {
+        return;
+    }
+
+    let min_run = opts.min_run;
     i = <*mut T>::max(i, s.add(1));
 
     while n > 0 {
         let offset = ptr_sub(i, s);
-        (s, n) = advance(s, n, offset.next_multiple_of(MIN_RUN) - MIN_RUN);
+        (s, n) = advance(s, n, offset.next_multiple_of(min_run) - min_run);
 
-        let len = usize::min(n, MIN_RUN);
-        insert_sort(s, usize::max(1, offset % MIN_RUN), len, less);
+        let len = usize::min(n, min_run);
+        S::sort_small(s, usize::max(1, offset % min_run), len, less);
 
         (s, n) = advance(s, n, len);
-        i = s.add(next_sorted_run(s, n, less));
+
+        let len = ptr_sub(s, i) + next_sorted_run(s, n, less);
+        i = i.add(len);
+
+        if len > 0 {
+            obs(Event::RunBuilt { len });
+        }
     }
 }
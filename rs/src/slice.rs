@@ -0,0 +1,50 @@
+use core::cmp::Ordering;
+
+/// Method-style access to the stable sorts, mirroring the sort methods on slices.
+///
+/// ```
+/// use dustsort::DustSortSlice;
+///
+/// let mut v = [3, 1, 2];
+/// v.dust_sort();
+/// assert_eq!(v, [1, 2, 3]);
+/// ```
+pub trait DustSortSlice<T> {
+    /// Sort the slice with [`sort`](crate::sort).
+    ///
+    /// Return `true` if it was already sorted, in which case it is left untouched.
+    fn dust_sort(&mut self) -> bool
+    where
+        T: Ord;
+
+    /// Sort the slice with a comparator `compare`, like [`sort_by`](crate::sort_by).
+    ///
+    /// Return `true` if it was already sorted with respect to `compare`.
+    fn dust_sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) -> bool;
+
+    /// Sort the slice with a key extraction function `f`, like
+    /// [`sort_by_key`](crate::sort_by_key).
+    ///
+    /// Return `true` if it was already sorted with respect to `f`.
+    fn dust_sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, f: F) -> bool;
+}
+
+impl<T> DustSortSlice<T> for [T] {
+    #[inline(always)]
+    fn dust_sort(&mut self) -> bool
+    where
+        T: Ord,
+    {
+        crate::sort(self)
+    }
+
+    #[inline(always)]
+    fn dust_sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) -> bool {
+        crate::sort_by(self, compare)
+    }
+
+    #[inline(always)]
+    fn dust_sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, f: F) -> bool {
+        crate::sort_by_key(self, f)
+    }
+}
@@ -0,0 +1,279 @@
+//! Strategies for sorting the short runs built at the small-sort level.
+
+use core::{mem::ManuallyDrop, ptr};
+
+use crate::{
+    dust::insert_sort,
+    util::{conditional, insert_left, search_right, Less},
+};
+
+/// A stable sorting routine for short runs.
+///
+/// # Safety
+///
+/// Implementations must sort stably, and must leave the region as a permutation of its original
+/// elements even if `less` panics.
+pub unsafe trait SmallSort {
+    /// The most comparisons per element a full sort with this strategy may make on top of
+    /// `n * ceil(log2 n)`, checked in debug builds with the `verify-bounds` feature. The default
+    /// disables the check.
+    const MAX_EXTRA_COMPARISONS: usize = usize::MAX;
+
+    /// Sort `s..s + n`, assuming the first `i` elements are sorted.
+    ///
+    /// # Safety
+    ///
+    /// The region `s..s + n` must be valid for reads and writes, and `i <= n`.
+    unsafe fn sort_small<T, F: FnMut(&T, &T) -> bool>(s: *mut T, i: usize, n: usize, less: &mut F);
+}
+
+/// Insertion sort with a linear scan. Preferred when comparisons are cheap. Regions of at most 4
+/// elements are sorted with a branchless network instead.
+pub struct LinearInsertion;
+
+unsafe impl SmallSort for LinearInsertion {
+    const MAX_EXTRA_COMPARISONS: usize = 12;
+
+    #[inline(always)]
+    unsafe fn sort_small<T, F: FnMut(&T, &T) -> bool>(s: *mut T, i: usize, n: usize, less: &mut F) {
+        if n <= MAX_TINY {
+            return sort_tiny(s, n, less);
+        }
+
+        insert_sort(s, i, n, less);
+    }
+}
+
+/// Insertion sort with a binary search. Preferred when comparisons are expensive.
+pub struct BinaryInsertion;
+
+unsafe impl SmallSort for BinaryInsertion {
+    const MAX_EXTRA_COMPARISONS: usize = 12;
+
+    unsafe fn sort_small<T, F: FnMut(&T, &T) -> bool>(s: *mut T, i: usize, n: usize, less: &mut F) {
+        for i in usize::max(i, 1)..n {
+            let cur = s.add(i);
+            insert_left(cur, i - search_right(s, i, cur, less));
+        }
+    }
+}
+
+/// Odd-even transposition sort. Only adjacent elements are exchanged, which keeps it stable, and
+/// each exchange is branchless.
+pub struct Network;
+
+unsafe impl SmallSort for Network {
+    const MAX_EXTRA_COMPARISONS: usize = 24;
+
+    unsafe fn sort_small<T, F: FnMut(&T, &T) -> bool>(s: *mut T, _: usize, n: usize, less: &mut F) {
+        // Two consecutive rounds without exchanges mean every adjacent pair is in order
+        let mut idle = 0;
+
+        for round in 0..n {
+            let mut swapped = false;
+
+            for j in (round % 2 + 1..n).step_by(2) {
+                swapped |= compare_exchange(s.add(j - 1), s.add(j), less);
+            }
+
+            idle = conditional(0, idle + 1, !swapped);
+
+            if idle == 2 {
+                break;
+            }
+        }
+    }
+}
+
+/// Inputs up to this length are sorted stably by [`sort_tiny`].
+const MAX_TINY: usize = 4;
+
+/// Stably sort `s..s + n` for `n <= MAX_TINY` with straight-line odd-even transposition, which
+/// avoids the mispredicted branches of insertion sort on random input.
+unsafe fn sort_tiny<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
+    let mut exchange = |i: usize| compare_exchange(s.add(i), s.add(i + 1), less);
+
+    match n {
+        2 => {
+            exchange(0);
+        }
+        3 => {
+            exchange(0);
+            exchange(1);
+            exchange(0);
+        }
+        4 => {
+            exchange(0);
+            exchange(2);
+            exchange(1);
+            exchange(0);
+            exchange(2);
+            exchange(1);
+        }
+        _ => {}
+    }
+}
+
+/// Inputs up to this length are sorted with a fixed network by [`sort_network`].
+pub(crate) const MAX_NETWORK: usize = 16;
+
+// Pairs `(i, j)` with `i < j` to compare-exchange in size-optimal sorting networks, one layer per
+// line. The network on 15 inputs is pruned from the one on 16.
+#[rustfmt::skip]
+const NETWORKS: [&[(u8, u8)]; MAX_NETWORK + 1] = [
+    &[],
+    &[],
+    &[
+        (0, 1),
+    ],
+    &[
+        (0, 2),
+        (0, 1),
+        (1, 2),
+    ],
+    &[
+        (0, 2), (1, 3),
+        (0, 1), (2, 3),
+        (1, 2),
+    ],
+    &[
+        (0, 3), (1, 4),
+        (0, 2), (1, 3),
+        (0, 1), (2, 4),
+        (1, 2), (3, 4),
+        (2, 3),
+    ],
+    &[
+        (0, 5), (1, 3), (2, 4),
+        (1, 2), (3, 4),
+        (0, 3), (2, 5),
+        (0, 1), (2, 3), (4, 5),
+        (1, 2), (3, 4),
+    ],
+    &[
+        (0, 6), (2, 3), (4, 5),
+        (0, 2), (1, 4), (3, 6),
+        (0, 1), (2, 5), (3, 4),
+        (1, 2), (4, 6),
+        (2, 3), (4, 5),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    &[
+        (0, 2), (1, 3), (4, 6), (5, 7),
+        (0, 4), (1, 5), (2, 6), (3, 7),
+        (0, 1), (2, 3), (4, 5), (6, 7),
+        (2, 4), (3, 5),
+        (1, 4), (3, 6),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    &[
+        (0, 3), (1, 7), (2, 5), (4, 8),
+        (0, 7), (2, 4), (3, 8), (5, 6),
+        (0, 2), (1, 3), (4, 5), (7, 8),
+        (1, 4), (3, 6), (5, 7),
+        (0, 1), (2, 4), (3, 5), (6, 8),
+        (2, 3), (4, 5), (6, 7),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    &[
+        (0, 8), (1, 9), (2, 7), (3, 5), (4, 6),
+        (0, 2), (1, 4), (5, 8), (7, 9),
+        (0, 3), (2, 4), (5, 7), (6, 9),
+        (0, 1), (3, 6), (8, 9),
+        (1, 5), (2, 3), (4, 8), (6, 7),
+        (1, 2), (3, 5), (4, 6), (7, 8),
+        (2, 3), (4, 5), (6, 7),
+        (3, 4), (5, 6),
+    ],
+    &[
+        (0, 9), (1, 6), (2, 4), (3, 7), (5, 8),
+        (0, 1), (3, 5), (4, 10), (6, 9), (7, 8),
+        (1, 3), (2, 5), (4, 7), (8, 10),
+        (0, 4), (1, 2), (3, 7), (5, 9), (6, 8),
+        (0, 1), (2, 6), (4, 5), (7, 8), (9, 10),
+        (2, 4), (3, 6), (5, 7), (8, 9),
+        (1, 2), (3, 4), (5, 6), (7, 8),
+        (2, 3), (4, 5), (6, 7),
+    ],
+    &[
+        (0, 8), (1, 7), (2, 6), (3, 11), (4, 10), (5, 9),
+        (0, 1), (2, 5), (3, 4), (6, 9), (7, 8), (10, 11),
+        (0, 2), (1, 6), (5, 10), (9, 11),
+        (0, 3), (1, 2), (4, 6), (5, 7), (8, 11), (9, 10),
+        (1, 4), (3, 5), (6, 8), (7, 10),
+        (1, 3), (2, 5), (6, 9), (8, 10),
+        (2, 3), (4, 5), (6, 7), (8, 9),
+        (4, 6), (5, 7),
+        (3, 4), (5, 6), (7, 8),
+    ],
+    &[
+        (0, 12), (1, 10), (2, 9), (3, 7), (5, 11), (6, 8),
+        (1, 6), (2, 3), (4, 11), (7, 9), (8, 10),
+        (0, 4), (1, 2), (3, 6), (7, 8), (9, 10), (11, 12),
+        (4, 6), (5, 9), (8, 11), (10, 12),
+        (0, 5), (3, 8), (4, 7), (6, 11), (9, 10),
+        (0, 1), (2, 5), (6, 9), (7, 8), (10, 11),
+        (1, 3), (2, 4), (5, 6), (9, 10),
+        (1, 2), (3, 4), (5, 7), (6, 8),
+        (2, 3), (4, 5), (6, 7), (8, 9),
+        (3, 4), (5, 6),
+    ],
+    &[
+        (0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13),
+        (0, 2), (1, 3), (4, 8), (5, 9), (10, 12), (11, 13),
+        (0, 4), (1, 2), (3, 7), (5, 8), (6, 10), (9, 13), (11, 12),
+        (0, 6), (1, 5), (3, 9), (4, 10), (7, 13), (8, 12),
+        (2, 10), (3, 11), (4, 6), (7, 9),
+        (1, 3), (2, 8), (5, 11), (6, 7), (10, 12),
+        (1, 4), (2, 6), (3, 5), (7, 11), (8, 10), (9, 12),
+        (2, 4), (3, 6), (5, 8), (7, 10), (9, 11),
+        (3, 4), (5, 6), (7, 8), (9, 10),
+        (6, 7),
+    ],
+    &[
+        (0, 13), (1, 12), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10),
+        (0, 5), (1, 7), (2, 9), (3, 4), (6, 13), (8, 14), (11, 12),
+        (0, 1), (2, 3), (4, 5), (6, 8), (7, 9), (10, 11), (12, 13),
+        (0, 2), (1, 3), (4, 10), (5, 11), (6, 7), (8, 9), (12, 14),
+        (1, 2), (3, 12), (4, 6), (5, 7), (8, 10), (9, 11), (13, 14),
+        (1, 4), (2, 6), (5, 8), (7, 10), (9, 13), (11, 14),
+        (2, 4), (3, 6), (9, 12), (11, 13),
+        (3, 5), (6, 8), (7, 9), (10, 12),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+    &[
+        (0, 13), (1, 12), (2, 15), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10),
+        (0, 5), (1, 7), (2, 9), (3, 4), (6, 13), (8, 14), (10, 15), (11, 12),
+        (0, 1), (2, 3), (4, 5), (6, 8), (7, 9), (10, 11), (12, 13), (14, 15),
+        (0, 2), (1, 3), (4, 10), (5, 11), (6, 7), (8, 9), (12, 14), (13, 15),
+        (1, 2), (3, 12), (4, 6), (5, 7), (8, 10), (9, 11), (13, 14),
+        (1, 4), (2, 6), (5, 8), (7, 10), (9, 13), (11, 14),
+        (2, 4), (3, 6), (9, 12), (11, 13),
+        (3, 5), (6, 8), (7, 9), (10, 12),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+];
+
+/// Sort `s..s + n` with a size-optimal sorting network, assuming `n <= MAX_NETWORK`.
+///
+/// Elements are exchanged over long distances, so this is not stable and can't implement
+/// [`SmallSort`]. Only use it where the order of equal elements doesn't matter.
+pub(crate) unsafe fn sort_network<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
+    for &(i, j) in NETWORKS[n] {
+        compare_exchange(s.add(i as usize), s.add(j as usize), less);
+    }
+}
+
+// Branchlessly order the elements at `a` and `b`, returning whether they were exchanged.
+#[inline(always)]
+unsafe fn compare_exchange<T, F: Less<T>>(a: *mut T, b: *mut T, less: &mut F) -> bool {
+    let swap = less(&*b, &*a);
+
+    let tmp = ManuallyDrop::new(conditional(b, a, swap).read());
+    ptr::copy(conditional(a, b, swap), a, 1);
+    ptr::copy_nonoverlapping(&*tmp, b, 1);
+
+    swap
+}
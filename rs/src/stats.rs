@@ -0,0 +1,66 @@
+use core::{cell::Cell, cmp::Ordering};
+use std::thread_local;
+
+use crate::{options::SortOptions, small_sort::LinearInsertion, sort_common};
+
+thread_local! {
+    // Element moves made on this thread since the innermost active `sort_instrumented` call
+    // started, or `None` outside of one
+    static MOVES: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Record `cnt` element moves, if a call to [`sort_instrumented`] on this thread is counting them.
+#[inline(always)]
+pub fn count_moves(cnt: usize) {
+    MOVES.with(|moves| {
+        if let Some(sum) = moves.get() {
+            moves.set(Some(sum + cnt));
+        }
+    });
+}
+
+// Restores the count of an enclosing `sort_instrumented` call when dropped, even while unwinding
+struct Counting(Option<usize>);
+
+impl Drop for Counting {
+    fn drop(&mut self) {
+        MOVES.with(|moves| moves.set(self.0));
+    }
+}
+
+/// Counts of the work done by a call to [`sort_instrumented`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SortStats {
+    /// The number of times the comparator was called.
+    pub comparisons: usize,
+
+    /// The approximate number of elements moved, counting each element written to its new place
+    /// or to scratch memory. Only moves made by this call are counted, not those of other sorts,
+    /// including any run by the comparator.
+    pub moves: usize,
+}
+
+/// Sort `v` stably with a comparator `compare`, like [`sort_by`](crate::sort_by), and return the
+/// work done.
+pub fn sort_instrumented<T, F: FnMut(&T, &T) -> Ordering>(
+    v: &mut [T],
+    mut compare: F,
+) -> SortStats {
+    let mut comparisons = 0;
+    let counting = Counting(MOVES.with(|m| m.replace(Some(0))));
+
+    sort_common::<LinearInsertion, _, _>(v, &SortOptions::DEFAULT, &mut |x, y| {
+        comparisons += 1;
+
+        // Set the count aside while the comparator runs, in case it sorts something itself
+        let sum = MOVES.with(|m| m.replace(None));
+        let less = compare(x, y) == Ordering::Less;
+        MOVES.with(|m| m.set(sum));
+        less
+    });
+
+    let moves = MOVES.with(Cell::get).unwrap_or(0);
+    drop(counting);
+
+    SortStats { comparisons, moves }
+}
@@ -0,0 +1,45 @@
+use super::Rng;
+use crate::small_sort::{BinaryInsertion, LinearInsertion, Network, SmallSort};
+
+// Insertion sort which then compares every pair of elements once more, while claiming the same
+// slack as the real insertion sorts.
+struct Wasteful;
+
+unsafe impl SmallSort for Wasteful {
+    const MAX_EXTRA_COMPARISONS: usize = 12;
+
+    unsafe fn sort_small<T, F: FnMut(&T, &T) -> bool>(s: *mut T, i: usize, n: usize, less: &mut F) {
+        LinearInsertion::sort_small(s, i, n, less);
+
+        for a in 0..n {
+            for b in a + 1..n {
+                debug_assert!(!less(&*s.add(b), &*s.add(a)));
+            }
+        }
+    }
+}
+
+fn check<S: SmallSort>() {
+    let mut rng = Rng::new(321);
+
+    for n in [10, 100, 1000, 2000, 10_000, 50_000] {
+        for range in [4, 100, n as u32, u32::MAX] {
+            let mut v = rng.keys(n, range);
+            crate::sort_by_with::<S, _, _>(&mut v, u32::cmp);
+            assert!(crate::is_sorted(&v));
+        }
+    }
+}
+
+#[test]
+fn within_bound() {
+    check::<LinearInsertion>();
+    check::<BinaryInsertion>();
+    check::<Network>();
+}
+
+#[test]
+#[should_panic = "comparisons, over"]
+fn wasteful_small_sort() {
+    check::<Wasteful>();
+}
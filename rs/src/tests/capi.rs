@@ -0,0 +1,53 @@
+use core::ptr;
+use std::vec::Vec;
+
+use super::Rng;
+
+// The entry points as declared in `include/dustsort.h`, linked by symbol name like a C caller would
+extern "C" {
+    fn dustsort_i32(ptr: *mut i32, len: usize);
+    fn dustsort_i64(ptr: *mut i64, len: usize);
+    fn dustsort_u32(ptr: *mut u32, len: usize);
+    fn dustsort_u64(ptr: *mut u64, len: usize);
+    fn dustsort_f64(ptr: *mut f64, len: usize);
+}
+
+// Sort values made from random bits with `c_sort` and with `std_sort`, then compare the bits.
+fn check<T: Copy>(
+    from_bits: fn(u64) -> T,
+    to_bits: fn(T) -> u64,
+    c_sort: unsafe extern "C" fn(*mut T, usize),
+    std_sort: fn(&mut [T]),
+) {
+    let mut rng = Rng::new(277);
+
+    for n in [1, 2, 10, 100, 1000, 10_000] {
+        let mut v: Vec<_> = (0..n).map(|_| from_bits(rng.next())).collect();
+        let mut expected = v.clone();
+
+        unsafe { c_sort(v.as_mut_ptr(), v.len()) };
+        std_sort(&mut expected);
+        assert!(v
+            .into_iter()
+            .map(to_bits)
+            .eq(expected.into_iter().map(to_bits)));
+    }
+
+    // Neither a null pointer nor an empty array is touched
+    unsafe {
+        c_sort(ptr::null_mut(), 0);
+        c_sort(ptr::null_mut(), 5);
+        c_sort(ptr::NonNull::dangling().as_ptr(), 0);
+    }
+}
+
+#[test]
+fn entry_points() {
+    check(|x| x as i32, |x| x as u64, dustsort_i32, |v| v.sort());
+    check(|x| x as i64, |x| x as u64, dustsort_i64, |v| v.sort());
+    check(|x| x as u32, |x| x as u64, dustsort_u32, |v| v.sort());
+    check(|x| x, |x| x, dustsort_u64, |v| v.sort());
+    check(f64::from_bits, f64::to_bits, dustsort_f64, |v| {
+        v.sort_by(f64::total_cmp)
+    });
+}
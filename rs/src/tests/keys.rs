@@ -0,0 +1,133 @@
+use core::cmp::Reverse;
+use std::vec::Vec;
+
+use super::{assert_permutation, Rng, Tracked};
+
+// Return the keys and ids of the elements in `v`.
+fn pairs(v: &[Tracked<0>]) -> Vec<(u32, u32)> {
+    v.iter().map(|x| (x.key, x.id)).collect()
+}
+
+#[test]
+fn sort_reverse_stable_by_key() {
+    let mut rng = Rng::new(317);
+
+    for n in [0, 1, 2, 100, 1000] {
+        for range in [3, 1000] {
+            let keys = rng.keys(n, range);
+            let mut v = Tracked::<0>::from_keys(&keys);
+
+            // Equal keys come out last occurrence first
+            let mut expected = pairs(&v);
+            expected.sort_by_key(|&(key, id)| (key, Reverse(id)));
+
+            let strictly_ascending = keys.windows(2).all(|w| w[0] < w[1]);
+            assert_eq!(
+                crate::sort_reverse_stable_by_key(&mut v, |x| x.key),
+                strictly_ascending
+            );
+            assert_eq!(pairs(&v), expected, "n = {n}");
+            assert_permutation(v, n);
+        }
+    }
+
+    // Strictly ascending keys return early, and ascending keys with ties still reverse them
+    let mut v = Tracked::<0>::from_keys(&[1, 2, 3, 5]);
+    assert!(crate::sort_reverse_stable_by_key(&mut v, |x| x.key));
+    assert_eq!(pairs(&v), [(1, 0), (2, 1), (3, 2), (5, 3)]);
+    assert_permutation(v, 4);
+
+    let mut v = Tracked::<0>::from_keys(&[1, 2, 2, 5]);
+    assert!(!crate::sort_reverse_stable_by_key(&mut v, |x| x.key));
+    assert_eq!(pairs(&v), [(1, 0), (2, 2), (2, 1), (5, 3)]);
+    assert_permutation(v, 4);
+}
+
+#[test]
+fn sort_by_key_ref() {
+    use std::{format, string::String};
+
+    struct Person {
+        name: String,
+        id: usize,
+    }
+
+    // Name people after `keys`, numbering them in order
+    fn people(keys: &[u32]) -> Vec<Person> {
+        let names = keys.iter().map(|k| format!("name{k}"));
+        names
+            .zip(0..)
+            .map(|(name, id)| Person { name, id })
+            .collect()
+    }
+
+    let mut rng = Rng::new(279);
+
+    for n in [0, 1, 2, 100, 3000] {
+        let keys = rng.keys(n, 300);
+        let mut expected = people(&keys);
+        crate::sort_by_key(&mut expected, |x| x.name.clone());
+
+        // Borrowing the field, and as `str`
+        let mut v = people(&keys);
+        crate::sort_by_key_ref(&mut v, |x| &x.name);
+        assert!(v.iter().map(|x| x.id).eq(expected.iter().map(|x| x.id)));
+
+        let mut v = people(&keys);
+        crate::sort_by_key_ref(&mut v, |x| x.name.as_str());
+        assert!(v.iter().map(|x| x.id).eq(expected.iter().map(|x| x.id)));
+    }
+}
+
+#[test]
+fn sort_desc() {
+    let mut rng = Rng::new(281);
+
+    for n in [0, 1, 2, 100, 3000] {
+        for range in [3, 1000] {
+            let keys = rng.keys(n, range);
+            let halves: Vec<_> = keys.iter().map(|k| k / 2).collect();
+
+            // Descending keys, with equal ones in their original order
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let mut expected = pairs(&v);
+            expected.sort_by_key(|&(key, _)| Reverse(key));
+
+            assert_eq!(crate::sort_desc(&mut v), keys.is_sorted_by(|x, y| x >= y));
+            assert_eq!(pairs(&v), expected, "n = {n}");
+            assert_permutation(v, n);
+
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let mut expected = pairs(&v);
+            expected.sort_by_key(|&(key, _)| Reverse(key / 2));
+
+            let sorted = halves.is_sorted_by(|x, y| x >= y);
+            assert_eq!(crate::sort_desc_by_key(&mut v, |x| x.key / 2), sorted);
+            assert_eq!(pairs(&v), expected, "n = {n}");
+            assert_permutation(v, n);
+        }
+    }
+}
+
+#[test]
+fn sort_by_key_fn() {
+    let mut rng = Rng::new(300);
+    let table: Vec<u32> = rng.keys(1000, 50);
+
+    // Owns its lookup table, so it isn't `Copy` and is only ever lent to the sorts
+    let lookup = table.clone();
+    let key = move |x: &Tracked<0>| lookup[x.key as usize];
+
+    for n in [0, 1, 2, 100, 3000] {
+        let keys = rng.keys(n, 1000);
+        let mut expected: Vec<_> = keys.iter().copied().zip(0..).collect();
+        expected.sort_by_key(|&(k, _)| table[k as usize]);
+
+        for _ in 0..2 {
+            let mut v = Tracked::<0>::from_keys(&keys);
+            crate::sort_by_key_fn(&mut v, &key);
+            assert_eq!(pairs(&v), expected, "n = {n}");
+            assert_permutation(v, n);
+        }
+    }
+}
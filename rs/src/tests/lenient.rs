@@ -0,0 +1,128 @@
+use core::cmp::Ordering;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    vec::Vec,
+};
+
+use super::{assert_permutation, Rng, Tracked};
+
+// Sizes reaching insertion sort, merges through a buffer, and block merges without alloc
+const SIZES: [usize; 5] = [10, 100, 1000, 5000, 20_000];
+
+#[test]
+fn nan() {
+    let mut rng = Rng::new(266);
+
+    for n in SIZES {
+        // About one in eight values is NaN, which is neither less nor greater than anything
+        let v: Vec<_> = (0..n)
+            .map(|_| match rng.next() % 8 {
+                0 => f64::NAN,
+                k => (rng.next() % 1000) as f64 / k as f64,
+            })
+            .collect();
+
+        let mut sorted = v.clone();
+        crate::sort_lenient(&mut sorted);
+
+        let bits = |v: &[f64]| {
+            let mut bits: Vec<_> = v.iter().map(|x| x.to_bits()).collect();
+            bits.sort_unstable();
+            bits
+        };
+        assert!(bits(&sorted) == bits(&v), "elements lost or duplicated");
+    }
+}
+
+#[test]
+fn non_transitive() {
+    let mut rng = Rng::new(266);
+    let opts = crate::SortOptions::DEFAULT.lenient(true);
+
+    for n in SIZES {
+        let keys = rng.keys(n, 1000);
+
+        // Rock, paper, scissors: each residue mod 3 beats the next one
+        let mut v = Tracked::<0>::from_keys(&keys);
+        crate::sort_with_options(&mut v, opts, |a, b| (a.key + 1) % 3 == b.key % 3);
+        assert_permutation(v, n);
+
+        // Answers that don't even agree with earlier ones
+        let mut v = Tracked::<0>::from_keys(&keys);
+        crate::sort_with_options(&mut v, opts, |_, _| rng.next() & 1 == 0);
+        assert_permutation(v, n);
+    }
+}
+
+// The strict sort panics on the random comparator above, so `non_transitive` does reach the
+// lenient fallback
+#[cfg(not(feature = "alloc"))]
+#[test]
+#[should_panic = "Ord violated"]
+fn strict() {
+    let mut rng = Rng::new(266);
+    let mut v: Vec<_> = (0..20_000).collect();
+    crate::sort_by(&mut v, |_: &u32, _| match rng.next() & 1 {
+        0 => core::cmp::Ordering::Less,
+        _ => core::cmp::Ordering::Greater,
+    });
+}
+
+// Return a comparator of the given `kind` that answers inconsistently.
+fn adversary(kind: usize, seed: u64) -> impl FnMut(&Tracked<0>, &Tracked<0>) -> bool {
+    let mut rng = Rng::new(seed);
+    let mut calls = 0usize;
+
+    move |a, b| {
+        calls += 1;
+        match kind {
+            // Coin flips
+            0 => rng.next() & 1 == 0,
+            // Every element is less than every other
+            1 => true,
+            // The order reverses every 64 calls
+            2 => (a.key < b.key) != (calls / 64 % 2 == 1),
+            // About one pair in 16 answers the wrong way, but always the same way
+            _ => (a.key < b.key) != ((a.id ^ b.id).wrapping_mul(0x9e37_79b9) >> 28 == 0),
+        }
+    }
+}
+
+#[test]
+fn adversarial() {
+    let mut rng = Rng::new(298);
+    let lenient = crate::SortOptions::DEFAULT.lenient(true);
+
+    for n in SIZES {
+        let keys = rng.keys(n, 1000);
+        let log = (usize::BITS - (n - 1).leading_zeros()) as usize;
+
+        for kind in 0..4 {
+            // The lenient sort finishes, within the comparisons of a consistent sort
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let (mut cmps, mut less) = (0, adversary(kind, n as u64));
+
+            crate::sort_with_options(&mut v, lenient, |a, b| {
+                cmps += 1;
+                less(a, b)
+            });
+            assert_permutation(v, n);
+            assert!(
+                cmps <= 2 * n * log,
+                "adversary {kind} took {cmps} comparisons to sort {n} elements"
+            );
+
+            // The strict sort may panic instead, but still keeps every element
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let mut less = adversary(kind, n as u64);
+
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                crate::sort_by(&mut v, |a, b| match less(a, b) {
+                    true => Ordering::Less,
+                    false => Ordering::Greater,
+                });
+            }));
+            assert_permutation(v, n);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::{string::String, vec::Vec};
+
+use super::{assert_permutation, assert_stable, Rng, Tracked};
+
+// Return `keys` wrapped with the halves before and after `mid` each stably sorted.
+fn sorted_halves(keys: &[u32], mid: usize) -> Vec<Tracked<0>> {
+    let mut v = Tracked::from_keys(keys);
+    v[..mid].sort();
+    v[mid..].sort();
+    v
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn extend_sorted() {
+    let mut rng = Rng::new(333);
+
+    for (n, k) in [
+        (0, 5),
+        (5, 0),
+        (1, 1),
+        (100, 3),
+        (3, 100),
+        (1000, 1000),
+        (5000, 40),
+    ] {
+        let keys = rng.keys(n + k, 50);
+        let mut v = Tracked::<0>::from_keys(&keys);
+        let new = v.split_off(n);
+        crate::sort(&mut v);
+
+        crate::extend_sorted(&mut v, new);
+        assert_stable(&v);
+        assert_permutation(v, n + k);
+    }
+}
+
+#[test]
+fn merge_sorted() {
+    let mut rng = Rng::new(252);
+
+    for n in [0usize, 1, 2, 10, 100, 600, 3000] {
+        for mid in [0, 1, n / 3, n / 2, n.saturating_sub(1), n] {
+            let keys = rng.keys(n, 40);
+            let mid = usize::min(mid, n);
+
+            let mut v = sorted_halves(&keys, mid);
+            crate::merge_sorted(&mut v, mid);
+            assert_stable(&v);
+            assert_permutation(v, n);
+
+            let mut v = sorted_halves(&keys, mid);
+            crate::merge_sorted_by(&mut v, mid, |a, b| a.key.cmp(&b.key));
+            assert_stable(&v);
+            assert_permutation(v, n);
+
+            let mut v = sorted_halves(&keys, mid);
+            crate::merge_sorted_by_key(&mut v, mid, |a| a.key);
+            assert_stable(&v);
+            assert_permutation(v, n);
+        }
+    }
+
+    // Every element on the right is less than every one on the left, so the merge is a rotation
+    for n in [2, 100, 1000] {
+        let keys: Vec<_> = (n / 2..n).chain(0..n / 2).collect();
+        let mut v = Tracked::<0>::from_keys(&keys);
+        crate::merge_sorted(&mut v, keys.len() - n as usize / 2);
+        assert_stable(&v);
+        assert_permutation(v, keys.len());
+    }
+}
+
+#[test]
+#[should_panic = "mid > len"]
+fn merge_sorted_mid_out_of_bounds() {
+    crate::merge_sorted(&mut [1, 2, 3], 4);
+}
+
+#[test]
+fn merge_lazy_skewed() {
+    // `log2(100)` rounded up
+    const LOG_RATIO: usize = 7;
+
+    let mut rng = Rng::new(271);
+
+    for small in [1, 10, 50] {
+        let big = 100 * small;
+
+        for (n1, n2) in [(small, big), (big, small)] {
+            let keys = rng.keys(n1 + n2, 1000);
+            let mut v = sorted_halves(&keys, n1);
+            let mut cmps = 0;
+
+            unsafe {
+                crate::merge::merge_lazy(
+                    v.as_mut_ptr(),
+                    n1,
+                    n2,
+                    &mut |a: &Tracked<0>, b: &Tracked<0>| {
+                        cmps += 1;
+                        a.key < b.key
+                    },
+                );
+            }
+
+            // Galloping takes `O(log d)` comparisons, and at most one rotation, per block of `d`
+            // elements, so both stay proportional to the shorter run
+            let max = 2 * small * (LOG_RATIO + 2);
+            assert!(
+                cmps <= max,
+                "merging {n1} + {n2} took {cmps} comparisons, over {max}"
+            );
+            assert_stable(&v);
+            assert_permutation(v, n1 + n2);
+        }
+    }
+}
+
+#[test]
+fn merge_sorted_strings() {
+    let mut rng = Rng::new(289);
+    let mut words = |n| {
+        let mut words: Vec<_> = rng
+            .keys(n, 40)
+            .iter()
+            .map(|k| std::format!("word{k}"))
+            .collect();
+        words.sort();
+        words
+    };
+
+    // Both sides of the switch to block rotations at 512 elements
+    for (n1, n2) in [(3, 5), (100, 300), (600, 600), (2000, 50)] {
+        let mut v: Vec<String> = words(n1);
+        v.extend(words(n2));
+
+        // Equal strings are told apart by where their contents live
+        let expected = {
+            let mut v: Vec<_> = v.iter().map(|s| (s.clone(), s.as_ptr())).collect();
+            v.sort_by(|a, b| a.0.cmp(&b.0));
+            v
+        };
+
+        crate::merge_sorted(&mut v, n1);
+        assert!(
+            v.iter().map(|s| (s.clone(), s.as_ptr())).eq(expected),
+            "{n1} + {n2} strings merged unstably"
+        );
+    }
+}
+
+// Return `keys` wrapped with each run between `boundaries` stably sorted.
+fn sorted_runs(keys: &[u32], boundaries: &[usize]) -> Vec<Tracked<0>> {
+    let mut v = Tracked::from_keys(keys);
+    let mut l = 0;
+
+    for &r in boundaries.iter().chain([&keys.len()]) {
+        v[l..r].sort();
+        l = r;
+    }
+
+    v
+}
+
+#[test]
+fn merge_runs() {
+    let mut rng = Rng::new(290);
+
+    for n in [0usize, 1, 7, 100, 1000, 5000] {
+        let keys = rng.keys(n, 50);
+
+        // One run, two uneven runs, and many runs of equal length
+        let mut cases = std::vec![std::vec![], std::vec![n / 3]];
+        for len in [1, 3, 32, 100] {
+            cases.push((len..n).step_by(len).collect());
+        }
+
+        for boundaries in cases {
+            // With and without empty runs at both ends
+            let mut padded: Vec<_> = [0].iter().chain(&boundaries).chain([&n]).copied().collect();
+            padded.dedup();
+
+            for boundaries in [boundaries, padded] {
+                let mut v = sorted_runs(&keys, &boundaries);
+                crate::merge_runs(&mut v, &boundaries);
+                assert_stable(&v);
+                assert_permutation(v, n);
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic = "boundaries not strictly increasing"]
+fn merge_runs_decreasing() {
+    crate::merge_runs(&mut [1, 2, 3, 4], &[3, 1]);
+}
+
+#[test]
+#[should_panic = "boundaries not strictly increasing"]
+fn merge_runs_repeated() {
+    crate::merge_runs(&mut [1, 2, 3, 4], &[2, 2]);
+}
+
+#[test]
+#[should_panic = "boundary > len"]
+fn merge_runs_out_of_bounds() {
+    crate::merge_runs(&mut [1, 2, 3, 4], &[2, 5]);
+}
+
+#[test]
+fn sort_hint_runs() {
+    let mut rng = Rng::new(330);
+
+    for n in [0, 1, 100, 1000] {
+        let keys = rng.keys(n, 50);
+
+        for len in [1, 7, 64, 1000] {
+            let boundaries: Vec<_> = (len..n).step_by(len).collect();
+            let mut v = sorted_runs(&keys, &boundaries);
+            crate::sort_hint_runs(&mut v, &boundaries);
+            assert_stable(&v);
+            assert_permutation(v, n);
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic = "run not sorted"]
+fn sort_hint_runs_unsorted() {
+    crate::sort_hint_runs(&mut [1, 3, 2, 0, 4], &[1, 3]);
+}
+
+#[test]
+fn sort_chunks() {
+    let mut rng = Rng::new(315);
+
+    // Lengths that leave a short last chunk, or none
+    for (n, chunk) in [
+        (0, 1),
+        (1, 4),
+        (10, 3),
+        (1000, 64),
+        (1000, 100),
+        (5000, 999),
+    ] {
+        let keys = rng.keys(n, 50);
+        let mut v = Tracked::<0>::from_keys(&keys);
+        let mut chunks = Vec::new();
+
+        crate::sort_chunks(&mut v, chunk, |c| {
+            assert_stable(c);
+            chunks.push(c.len());
+        });
+
+        let full = n / chunk;
+        assert_eq!(chunks.len(), n.div_ceil(chunk));
+        assert!(chunks[..full].iter().all(|&len| len == chunk));
+        assert!(chunks[full..].iter().all(|&len| len == n % chunk));
+
+        let boundaries: Vec<_> = (chunk..n).step_by(chunk).collect();
+        crate::merge_runs(&mut v, &boundaries);
+        assert_stable(&v);
+        assert_permutation(v, n);
+    }
+}
+
+#[test]
+#[should_panic = "chunk size must be non-zero"]
+fn sort_chunks_zero() {
+    crate::sort_chunks(&mut [1, 2], 0, |_| {});
+}
@@ -0,0 +1,206 @@
+use std::{
+    boxed::Box,
+    cell::Cell,
+    cmp::Ordering,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+    thread_local,
+    vec::Vec,
+};
+
+#[cfg(all(feature = "verify-bounds", debug_assertions))]
+mod bounds;
+#[cfg(feature = "c-api")]
+mod capi;
+mod keys;
+mod lenient;
+mod merge;
+mod observe;
+mod panic_safety;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod partial;
+mod partition;
+mod perm;
+mod primitives;
+mod records;
+mod scan;
+mod search;
+mod small_sort;
+mod sort;
+#[cfg(feature = "stats")]
+mod stats;
+mod util;
+#[cfg(all(feature = "verify", debug_assertions))]
+mod verify;
+
+thread_local! {
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+}
+
+// Payload of panics raised on purpose, which are kept out of the test output
+const INJECTED: &str = "injected comparator panic";
+
+/// A xorshift generator, so that tests are reproducible without extra dependencies.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed | 1)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Return `n` keys below `range`.
+    pub fn keys(&mut self, n: usize, range: u32) -> Vec<u32> {
+        (0..n)
+            .map(|_| (self.next() % range as u64) as u32)
+            .collect()
+    }
+}
+
+/// An element sorted by `key` alone, which counts how many instances are alive so that lost or
+/// duplicated elements show up. `PAD` words of padding select the element size.
+pub struct Tracked<const PAD: usize> {
+    pub key: u32,
+    pub id: u32,
+    #[allow(dead_code)]
+    pad: [u64; PAD],
+}
+
+impl<const PAD: usize> Tracked<PAD> {
+    /// Wrap `keys`, numbering the elements in order.
+    pub fn from_keys(keys: &[u32]) -> Vec<Self> {
+        LIVE.with(|c| c.set(c.get() + keys.len() as isize));
+
+        let ids = 0..keys.len() as u32;
+        keys.iter()
+            .zip(ids)
+            .map(|(&key, id)| Self {
+                key,
+                id,
+                pad: [0; PAD],
+            })
+            .collect()
+    }
+}
+
+impl<const PAD: usize> PartialEq for Tracked<PAD> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<const PAD: usize> Eq for Tracked<PAD> {}
+
+impl<const PAD: usize> PartialOrd for Tracked<PAD> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const PAD: usize> Ord for Tracked<PAD> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl<const PAD: usize> Drop for Tracked<PAD> {
+    fn drop(&mut self) {
+        LIVE.with(|c| c.set(c.get() - 1));
+    }
+}
+
+/// Assert that `v` holds each element wrapped from `n` keys exactly once, then drop it and assert
+/// that no other instance is alive.
+pub fn assert_permutation<const PAD: usize>(v: Vec<Tracked<PAD>>, n: usize) {
+    let mut ids: Vec<_> = v.iter().map(|x| x.id).collect();
+    ids.sort_unstable();
+    assert!(
+        ids.iter().copied().eq(0..n as u32),
+        "elements lost or duplicated"
+    );
+
+    drop(v);
+    assert_eq!(
+        LIVE.with(Cell::get),
+        0,
+        "elements dropped the wrong number of times"
+    );
+}
+
+/// Assert that `v` is sorted by key, with equal keys in their original order.
+pub fn assert_stable<const PAD: usize>(v: &[Tracked<PAD>]) {
+    for w in v.windows(2) {
+        assert!(
+            (w[0].key, w[0].id) < (w[1].key, w[1].id),
+            "not stably sorted"
+        );
+    }
+}
+
+// The comparator type the sweeps pass to the sort under test
+pub type DynLess<'a, const PAD: usize> = &'a mut dyn FnMut(&Tracked<PAD>, &Tracked<PAD>) -> bool;
+
+/// Sort `keys` with `sort` once per comparison it takes, making the comparator panic at that
+/// comparison, or at about `points` comparisons spread over the whole range. Check that every
+/// element survives each panic exactly once, and that the sort without a panic is stable.
+pub fn panic_sweep<const PAD: usize>(
+    keys: &[u32],
+    points: usize,
+    mut sort: impl FnMut(&mut [Tracked<PAD>], DynLess<PAD>),
+) {
+    let mut v = Tracked::<PAD>::from_keys(keys);
+    let mut cmps = 0;
+    sort(&mut v, &mut |a, b| {
+        cmps += 1;
+        a.key < b.key
+    });
+    assert_stable(&v);
+    assert_permutation(v, keys.len());
+
+    panic_at(keys, (1..=cmps).step_by(usize::max(1, cmps / points)), sort);
+}
+
+/// Sort `keys` with `sort` once for each comparison index in `ks`, making the comparator panic at
+/// that comparison. Check that it happened and that every element survives exactly once.
+pub fn panic_at<const PAD: usize>(
+    keys: &[u32],
+    ks: impl IntoIterator<Item = usize>,
+    mut sort: impl FnMut(&mut [Tracked<PAD>], DynLess<PAD>),
+) {
+    static QUIET: Once = Once::new();
+
+    QUIET.call_once(|| {
+        let hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            if info.payload().downcast_ref::<&str>() != Some(&INJECTED) {
+                hook(info);
+            }
+        }));
+    });
+
+    for k in ks {
+        let mut v = Tracked::<PAD>::from_keys(keys);
+        let mut left = k;
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            sort(&mut v, &mut |a, b| {
+                left -= 1;
+                if left == 0 {
+                    panic::panic_any(INJECTED);
+                }
+                a.key < b.key
+            })
+        }));
+
+        assert!(res.is_err(), "comparison {k} never happened");
+        assert_permutation(v, keys.len());
+    }
+}
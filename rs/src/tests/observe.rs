@@ -0,0 +1,78 @@
+use std::vec::Vec;
+
+use super::Rng;
+use crate::Event;
+
+// Sort `v` and return the events reported, with consecutive `RunBuilt` events combined except for
+// the first, which reports the natural run at the start.
+fn events(v: &mut [u32]) -> Vec<Event> {
+    let mut events = Vec::new();
+
+    crate::sort_with_observer(v, u32::lt, |e| match (events.len(), events.last_mut(), e) {
+        (2.., Some(Event::RunBuilt { len }), Event::RunBuilt { len: more }) => *len += more,
+        _ => events.push(e),
+    });
+
+    assert!(crate::is_sorted(v));
+    events
+}
+
+#[test]
+fn event_sequence() {
+    use Event::*;
+
+    let mut rng = Rng::new(299);
+
+    // Already sorted, so no events are reported
+    assert_eq!(events(&mut (0..5000).collect::<Vec<_>>()), []);
+
+    // A short tail after a long natural run is sorted on its own and merged with rotations
+    let mut v: Vec<_> = (0..5000).chain(rng.keys(100, 5000)).collect();
+    assert_eq!(
+        events(&mut v),
+        [
+            RunBuilt { len: 5000 },
+            FellBackToSpecial,
+            RunBuilt { len: 100 }
+        ]
+    );
+
+    // A natural run followed by random keys
+    let mut v: Vec<_> = (0..3000).chain(rng.keys(2000, 100_000)).collect();
+    let e = events(&mut v);
+    let RunBuilt { len: head } = e[0] else {
+        panic!("{e:?}")
+    };
+    assert!(head >= 3000, "{e:?}");
+
+    if cfg!(feature = "alloc") {
+        // Merged through an allocated buffer
+        assert_eq!(e[1..], [RunBuilt { len: 5000 - head }]);
+    } else {
+        // Merged through keys collected from the runs once they are built. The first few keys are
+        // set aside from the end beforehand, to tell whether there are enough of them.
+        let (keys, block_len) = (165, 64);
+        let early = crate::dust::MIN_DISTINCT;
+        assert_eq!(
+            e[1..],
+            [
+                RunBuilt {
+                    len: 5000 - head - early
+                },
+                KeysCollected { count: keys },
+                BlockMergeStarted { block_len }
+            ]
+        );
+    }
+
+    // Too few distinct keys to collect, so without alloc, the runs are merged with rotations
+    let mut v = rng.keys(5000, 8);
+    let e = events(&mut v);
+
+    let runs = match e[..] {
+        [RunBuilt { len: a }, RunBuilt { len: b }] if cfg!(feature = "alloc") => a + b,
+        [RunBuilt { len: a }, FellBackToSpecial, RunBuilt { len: b }] => a + b,
+        _ => panic!("{e:?}"),
+    };
+    assert_eq!(runs, 5000);
+}
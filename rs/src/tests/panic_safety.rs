@@ -0,0 +1,192 @@
+use std::vec::Vec;
+
+#[cfg(not(feature = "alloc"))]
+use super::{assert_permutation, panic_at, Tracked};
+use super::{panic_sweep, Rng};
+
+// Return `n` sorted keys with about `swaps` random pairs exchanged.
+fn nearly_sorted(rng: &mut Rng, n: usize, swaps: usize) -> Vec<u32> {
+    let mut keys: Vec<_> = (0..n as u32).collect();
+
+    for _ in 0..swaps {
+        let (a, b) = (rng.next() as usize % n, rng.next() as usize % n);
+        keys.swap(a, b);
+    }
+
+    keys
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn scratch_merge() {
+    let mut rng = Rng::new(259);
+
+    // Elements too large to ping-pong, and too many for the stack
+    for keys in [rng.keys(2000, 500), nearly_sorted(&mut rng, 2000, 40)] {
+        panic_sweep::<2>(&keys, 300, |v, less| {
+            crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+        });
+    }
+}
+
+#[test]
+fn scratch_merge_external() {
+    let mut rng = Rng::new(259);
+
+    for keys in [rng.keys(2000, 500), nearly_sorted(&mut rng, 2000, 40)] {
+        panic_sweep::<0>(&keys, 300, |v, less| {
+            let mut scratch = Vec::with_capacity(v.len() / 2);
+            crate::sort_with_scratch(v, scratch.spare_capacity_mut(), less);
+        });
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn ping_pong() {
+    let mut rng = Rng::new(335);
+
+    // A sorted head with an appended tail long enough not to be inserted with rotations
+    let mut appended = nearly_sorted(&mut rng, 3600, 0);
+    appended.extend(rng.keys(400, 4000));
+
+    for keys in [
+        rng.keys(2000, 500),
+        nearly_sorted(&mut rng, 2000, 40),
+        appended,
+    ] {
+        panic_sweep::<0>(&keys, 300, |v, less| {
+            crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+        });
+    }
+}
+
+// Without alloc, large enough inputs with enough distinct keys collect them into an internal
+// buffer, which the comparator can interrupt between insertions
+#[cfg(not(feature = "alloc"))]
+#[test]
+fn key_collection() {
+    use std::cell::Cell;
+
+    let mut rng = Rng::new(208);
+
+    // Binary searches below 4096 elements, a virtual merge with the buffer above
+    for (n, range) in [(2000, 200), (20_000, 1000)] {
+        let keys = rng.keys(n, range);
+
+        // Find how many comparisons the sort takes to collect keys
+        let (cmps, mut collected) = (Cell::new(0), 0);
+        let mut v = Tracked::<0>::from_keys(&keys);
+
+        crate::sort_with_observer(
+            &mut v,
+            |a, b| {
+                cmps.set(cmps.get() + 1);
+                a.key < b.key
+            },
+            |e| {
+                if let crate::Event::KeysCollected { .. } = e {
+                    collected = cmps.get();
+                }
+            },
+        );
+        assert_permutation(v, n);
+        assert!(collected > 0, "keys never collected");
+
+        panic_at::<0>(
+            &keys,
+            (1..=collected).step_by(collected / 300 + 1),
+            |v, less| {
+                crate::sort_with_observer(v, less, |_| {});
+            },
+        );
+    }
+}
+
+// Half the array fits on the stack, whether or not there is an allocator
+#[test]
+fn stack_merge() {
+    let mut rng = Rng::new(265);
+
+    for keys in [rng.keys(400, 100), nearly_sorted(&mut rng, 400, 10)] {
+        panic_sweep::<0>(&keys, 300, |v, less| {
+            crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+        });
+    }
+}
+
+// A short unsorted tail after a long sorted head is inserted with rotations
+#[test]
+fn appended_tail() {
+    let mut rng = Rng::new(265);
+    let mut keys = nearly_sorted(&mut rng, 3000, 0);
+    keys.extend(rng.keys(100, 3000));
+
+    panic_sweep::<0>(&keys, 300, |v, less| {
+        crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+    });
+    panic_sweep::<2>(&keys, 300, |v, less| {
+        crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+    });
+}
+
+// Without alloc, block merges through the collected keys, for both ways of collecting them
+#[cfg(not(feature = "alloc"))]
+#[test]
+fn block_merge() {
+    let mut rng = Rng::new(265);
+
+    for n in [2000, 6000] {
+        let keys = rng.keys(n, 5000);
+
+        panic_sweep::<0>(&keys, 100, |v, less| {
+            crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+        });
+        panic_sweep::<2>(&keys, 100, |v, less| {
+            crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+        });
+    }
+}
+
+// Without alloc, too few distinct keys for a block merge fall back to rotations
+#[cfg(not(feature = "alloc"))]
+#[test]
+fn few_distinct() {
+    let mut rng = Rng::new(265);
+    let keys = rng.keys(1500, 8);
+
+    panic_sweep::<0>(&keys, 100, |v, less| {
+        crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+    });
+    panic_sweep::<2>(&keys, 100, |v, less| {
+        crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+    });
+}
+
+#[test]
+fn in_place() {
+    use crate::small_sort::LinearInsertion;
+
+    let mut rng = Rng::new(265);
+
+    for keys in [rng.keys(2000, 500), nearly_sorted(&mut rng, 2000, 40)] {
+        panic_sweep::<0>(&keys, 300, |v, mut less| unsafe {
+            let opts = &crate::SortOptions::DEFAULT;
+            crate::dust::sort_in_place::<LinearInsertion, _, _>(
+                v.as_mut_ptr(),
+                v.len(),
+                opts,
+                &mut less,
+            );
+        });
+        panic_sweep::<2>(&keys, 300, |v, mut less| unsafe {
+            let opts = &crate::SortOptions::DEFAULT;
+            crate::dust::sort_in_place::<LinearInsertion, _, _>(
+                v.as_mut_ptr(),
+                v.len(),
+                opts,
+                &mut less,
+            );
+        });
+    }
+}
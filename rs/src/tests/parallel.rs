@@ -0,0 +1,81 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+    thread,
+    vec::Vec,
+};
+
+use super::{assert_permutation, assert_stable, Rng, Tracked};
+use crate::Spawn;
+
+// A spawner which runs the second closure on a scoped thread while fewer than `threads` threads
+// are busy, and otherwise runs both on the current thread.
+struct Threads {
+    idle: AtomicUsize,
+}
+
+impl Threads {
+    fn new(threads: usize) -> Self {
+        Self {
+            idle: AtomicUsize::new(threads - 1),
+        }
+    }
+}
+
+impl Spawn for Threads {
+    fn join<A: FnOnce() + Send, B: FnOnce() + Send>(&self, a: A, b: B) {
+        let claimed = self
+            .idle
+            .fetch_update(Relaxed, Relaxed, |idle| idle.checked_sub(1))
+            .is_ok();
+
+        if !claimed {
+            a();
+            return b();
+        }
+
+        thread::scope(|s| {
+            s.spawn(b);
+            a();
+        });
+        self.idle.fetch_add(1, Relaxed);
+    }
+}
+
+#[test]
+fn par_sort() {
+    let mut rng = Rng::new(296);
+
+    // Sizes around the sequential cutoff of 8192 elements and well above it
+    for n in [8192, 8193, 20_000, 100_000] {
+        for range in [10, 1000, u32::MAX] {
+            let keys = rng.keys(n, range);
+            let mut v = Tracked::<0>::from_keys(&keys);
+
+            crate::par_sort(&mut v, &Threads::new(4));
+            assert_stable(&v);
+            assert_permutation(v, n);
+        }
+    }
+}
+
+#[test]
+fn deterministic_across_threads() {
+    let mut rng = Rng::new(206);
+
+    for n in [20_000, 100_000] {
+        // Few distinct keys, so that merges split between many equal elements
+        let keys = rng.keys(n, 16);
+
+        let mut expected = Tracked::<0>::from_keys(&keys);
+        crate::sort(&mut expected);
+        let expected: Vec<_> = expected.into_iter().map(|x| x.id).collect();
+
+        for threads in [1, 2, 4, 8] {
+            let mut v = Tracked::<0>::from_keys(&keys);
+            crate::par_sort(&mut v, &Threads::new(threads));
+
+            let ids: Vec<_> = v.into_iter().map(|x| x.id).collect();
+            assert!(ids == expected, "{n} elements on {threads} threads");
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use core::cmp::Reverse;
+use std::vec::Vec;
+
+use super::{assert_permutation, Rng, Tracked};
+
+#[test]
+fn partial_sort() {
+    let mut rng = Rng::new(255);
+
+    for n in [0, 1, 5, 31, 100, 1000] {
+        for range in [3, 1000] {
+            let keys = rng.keys(n, range);
+            let mut sorted = keys.clone();
+            sorted.sort();
+
+            // Nothing, a few, all, and more than all of the elements
+            for k in [0, 1, n / 2, n.saturating_sub(1), n, n + 1, 2 * n + 5] {
+                let m = k.min(n);
+                let mut v = Tracked::<0>::from_keys(&keys);
+                crate::partial_sort(&mut v, k);
+                assert!(v[..m].iter().map(|x| x.key).eq(sorted[..m].iter().copied()));
+                assert_permutation(v, n);
+
+                let mut v = keys.clone();
+                crate::partial_sort_by(&mut v, k, |x, y| y.cmp(x));
+                assert!(
+                    v[..m].iter().eq(sorted.iter().rev().take(m)),
+                    "n = {n}, k = {k}"
+                );
+
+                let mut v = keys.clone();
+                crate::partial_sort_by_key(&mut v, k, |&x| x / 2);
+                let halves = v[..m].iter().map(|x| x / 2);
+                assert!(
+                    halves.eq(sorted[..m].iter().map(|x| x / 2)),
+                    "n = {n}, k = {k}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn k_smallest() {
+    let mut rng = Rng::new(268);
+
+    for n in [0, 1, 5, 31, 100, 1000] {
+        // Few distinct keys make ties decide which elements make the cut
+        for range in [3, 1000] {
+            let keys = rng.keys(n, range);
+
+            for k in [0, 1, 2, n / 4, n / 2, n, n + 1] {
+                let m = k.min(n);
+
+                let mut expected: Vec<_> = keys.iter().copied().zip(0u32..).collect();
+                expected.sort_by_key(|x| x.0);
+                let mut v = Tracked::<0>::from_keys(&keys);
+                let front = crate::k_smallest(&mut v, k);
+                assert!(front
+                    .iter()
+                    .map(|x| (x.key, x.id))
+                    .eq(expected[..m].iter().copied()));
+                assert_permutation(v, n);
+
+                // Earliest first among equal keys, the same as for the smallest
+                expected.sort_by_key(|x| (Reverse(x.0), x.1));
+                let mut v = Tracked::<0>::from_keys(&keys);
+                let front = crate::k_largest(&mut v, k);
+                assert!(front
+                    .iter()
+                    .map(|x| (x.key, x.id))
+                    .eq(expected[..m].iter().copied()));
+                assert_permutation(v, n);
+            }
+        }
+    }
+}
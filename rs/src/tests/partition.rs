@@ -0,0 +1,103 @@
+use std::vec::Vec;
+
+use super::{assert_permutation, Rng, Tracked};
+
+// Return the ids of the elements in `v`.
+fn ids(v: &[Tracked<0>]) -> Vec<u32> {
+    v.iter().map(|x| x.id).collect()
+}
+
+#[test]
+fn stable_partition() {
+    let mut rng = Rng::new(282);
+
+    type Pred = fn(&Tracked<0>) -> bool;
+
+    let preds: [(&str, Pred); 4] = [
+        ("all true", |_| true),
+        ("all false", |_| false),
+        ("alternating", |x| x.id & 1 == 0),
+        ("random", |x| x.key & 1 == 0),
+    ];
+
+    // Lengths on both sides of the insertion cutoff, and odd ones that split unevenly
+    for n in [0, 1, 2, 15, 16, 17, 100, 1001] {
+        let keys = rng.keys(n, 1000);
+
+        for (name, pred) in preds {
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let mut seen = Vec::new();
+
+            // Each side lists the ids satisfying `pred` or not, in their original order
+            let pass: Vec<_> = v.iter().filter(|x| pred(x)).map(|x| x.id).collect();
+            let fail: Vec<_> = v.iter().filter(|x| !pred(x)).map(|x| x.id).collect();
+
+            let k = crate::stable_partition(&mut v, |x| {
+                seen.push(x.id);
+                pred(x)
+            });
+
+            assert_eq!(ids(&v[..k]), pass, "{name}, n = {n}");
+            assert_eq!(ids(&v[k..]), fail, "{name}, n = {n}");
+
+            seen.sort_unstable();
+            assert!(
+                seen.into_iter().eq(0..n as u32),
+                "{name} not called once per element"
+            );
+            assert_permutation(v, n);
+        }
+    }
+}
+
+#[test]
+fn sort_bool_by() {
+    let mut rng = Rng::new(319);
+
+    for n in [0, 1, 16, 17, 100, 1001] {
+        let keys = rng.keys(n, 1000);
+
+        // Mostly false, balanced, and mostly true
+        for cut in [100, 500, 900] {
+            let f = |x: &Tracked<0>| x.key >= cut;
+
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let mut expected = ids(&v);
+            expected.sort_by_key(|&i| f(&v[i as usize]));
+
+            let k = crate::sort_bool_by(&mut v, f);
+            assert_eq!(k, keys.iter().filter(|&&x| x < cut).count());
+            assert_eq!(ids(&v), expected, "n = {n}, cut = {cut}");
+            assert_permutation(v, n);
+        }
+    }
+}
+
+#[test]
+fn sort_retain() {
+    let mut rng = Rng::new(305);
+
+    for n in [0, 1, 16, 17, 100, 1001] {
+        for range in [3, 1000] {
+            let keys = rng.keys(n, range);
+            let keep = |x: &Tracked<0>| !x.id.is_multiple_of(3);
+
+            // Filter, then sort stably what's kept
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let mut kept: Vec<_> = v
+                .iter()
+                .filter(|x| keep(x))
+                .map(|x| (x.key, x.id))
+                .collect();
+            kept.sort_by_key(|x| x.0);
+            let discarded: Vec<_> = v.iter().filter(|x| !keep(x)).map(|x| x.id).collect();
+
+            let k = crate::sort_retain(&mut v, keep);
+            assert!(v[..k].iter().map(|x| (x.key, x.id)).eq(kept));
+            assert_eq!(ids(&v[k..]), discarded, "n = {n}");
+
+            // The discarded elements are still in `v`, not dropped
+            assert_permutation(v, n);
+        }
+    }
+}
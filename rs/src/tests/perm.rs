@@ -0,0 +1,130 @@
+use super::{assert_permutation, assert_stable, Rng, Tracked};
+
+#[test]
+fn sort_by_cached_key() {
+    let mut rng = Rng::new(253);
+
+    for n in [0, 1, 2, 100, 5000] {
+        let keys = rng.keys(n, 50);
+        let mut v = Tracked::<0>::from_keys(&keys);
+        let mut calls = 0;
+
+        crate::sort_by_cached_key(&mut v, |x| {
+            calls += 1;
+            x.key
+        });
+
+        // Without alloc, this is `sort_by_key`, which extracts keys once per comparison
+        if cfg!(feature = "alloc") {
+            assert_eq!(calls, n);
+        }
+
+        assert_stable(&v);
+        assert_permutation(v, n);
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn argsort() {
+    use std::vec::Vec;
+
+    let mut rng = Rng::new(256);
+    assert!(crate::argsort::<u32>(&[]).is_empty());
+
+    for n in [1, 2, 100, 5000] {
+        let keys = rng.keys(n, 50);
+
+        // Equal keys are ordered by index
+        let mut expected: Vec<_> = (0..n as u32).collect();
+        expected.sort_by_key(|&i| keys[i as usize]);
+
+        assert_eq!(crate::argsort(&keys), expected);
+
+        let expected: Vec<_> = expected.iter().map(|&i| i as usize).collect();
+        assert_eq!(crate::argsort_usize(&keys), expected);
+    }
+}
+
+#[test]
+fn sort_by_rank() {
+    let mut rng = Rng::new(326);
+
+    for n in [0, 1, 2, 100, 5000] {
+        for range in [1, 3, 1000] {
+            let keys = rng.keys(n, range);
+            let mut v = Tracked::<0>::from_keys(&keys);
+
+            assert_eq!(crate::sort_by_rank(&mut v, |x| x.key), keys.is_sorted());
+            assert_stable(&v);
+            assert_permutation(v, n);
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn sort_by_small_rank() {
+    let mut rng = Rng::new(326);
+
+    for n in [0, 1, 2, 100, 5000] {
+        for range in [1, 3, 1000] {
+            let keys = rng.keys(n, range);
+            let top = keys.iter().copied().max().unwrap_or(0);
+
+            // Counting when `max_rank <= n`, and sorting the ranks with a looser bound
+            for max_rank in [top, top + 1, n as u32, n as u32 + 1, u32::MAX] {
+                if max_rank < top {
+                    continue;
+                }
+
+                let mut v = Tracked::<0>::from_keys(&keys);
+                let mut calls = 0;
+
+                let res = crate::sort_by_small_rank(
+                    &mut v,
+                    |x| {
+                        calls += 1;
+                        x.key
+                    },
+                    max_rank,
+                );
+
+                assert_eq!(res, keys.is_sorted(), "n = {n}, max_rank = {max_rank}");
+                assert_eq!(calls, n);
+                assert_stable(&v);
+                assert_permutation(v, n);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+#[should_panic = "rank > max_rank"]
+fn sort_by_small_rank_out_of_range() {
+    crate::sort_by_small_rank(&mut [0, 3, 1, 2], |&x| x, 2);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn sort_by_key_collect() {
+    use std::{format, string::String, vec::Vec};
+
+    let mut rng = Rng::new(328);
+
+    for n in [0, 1, 2, 100, 5000] {
+        let keys = rng.keys(n, 50);
+        let mut v = Tracked::<0>::from_keys(&keys);
+        let f = |x: &Tracked<0>| format!("{:03}", x.key);
+
+        let collected: Vec<String> = crate::sort_by_key_collect(&mut v, f);
+
+        // Each key sits at the index of its element, in order
+        assert_eq!(collected.len(), n);
+        assert!(collected.iter().zip(&v).all(|(k, x)| *k == f(x)));
+        assert!(collected.is_sorted());
+        assert_stable(&v);
+        assert_permutation(v, n);
+    }
+}
@@ -0,0 +1,79 @@
+use std::vec::Vec;
+
+use super::Rng;
+
+// Sizes on both sides of the stack buffer and of the small-run thresholds
+const SIZES: [usize; 9] = [0, 1, 7, 64, 100, 1000, 2049, 10_000, 100_000];
+
+// Return `n` random bit patterns, drawn from `range` values that start with the extremes.
+fn bits(rng: &mut Rng, n: usize, range: u32) -> Vec<u32> {
+    let extremes = [0, u32::MAX, i32::MAX as u32, i32::MIN as u32, 1];
+    let values: Vec<_> = (0..range.min(1 << 16))
+        .map(|i| match extremes.get(i as usize) {
+            Some(&x) => x,
+            None => rng.next() as u32,
+        })
+        .collect();
+
+    (0..n)
+        .map(|_| match range {
+            u32::MAX => rng.next() as u32,
+            _ => values[rng.next() as usize % values.len()],
+        })
+        .collect()
+}
+
+// Sort values made from random bit patterns with `sort`, and compare the bits against `std_sort`.
+// Inputs are shuffled, and sorted with every 37th element replaced.
+fn check<T: Copy>(
+    from_bits: fn(u32) -> T,
+    to_bits: fn(T) -> u32,
+    sort: fn(&mut [T]) -> bool,
+    std_sort: fn(&mut [T]),
+) {
+    let mut rng = Rng::new(272);
+
+    for n in SIZES {
+        for range in [2, 16, 1000, u32::MAX] {
+            let shuffled: Vec<_> = bits(&mut rng, n, range)
+                .into_iter()
+                .map(from_bits)
+                .collect();
+
+            let mut noisy = shuffled.clone();
+            std_sort(&mut noisy);
+            for x in noisy.iter_mut().step_by(37) {
+                *x = from_bits(rng.next() as u32);
+            }
+
+            for v in [shuffled, noisy] {
+                let (mut actual, mut expected) = (v.clone(), v);
+                sort(&mut actual);
+                std_sort(&mut expected);
+
+                let (actual, expected) = (actual.into_iter(), expected.into_iter());
+                assert!(
+                    actual.map(to_bits).eq(expected.map(to_bits)),
+                    "n = {n}, range = {range}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn sort_i32() {
+    check(|x| x as i32, |x| x as u32, crate::sort_i32, |v| v.sort());
+}
+
+#[test]
+fn sort_u32() {
+    check(|x| x, |x| x, crate::sort_u32, |v| v.sort());
+}
+
+#[test]
+fn sort_floats_f32() {
+    check(f32::from_bits, f32::to_bits, crate::sort_floats_f32, |v| {
+        v.sort_by(f32::total_cmp)
+    });
+}
@@ -0,0 +1,31 @@
+use std::vec::Vec;
+
+use super::Rng;
+
+#[test]
+fn sort_records_matches_std() {
+    let mut rng = Rng::new(205);
+
+    for (record_len, key_len) in [(1, 1), (7, 3), (7, 0), (8, 8), (12, 5), (33, 9)] {
+        for n in [0, 1, 2, 31, 32, 33, 100, 1000] {
+            // Keys over a small alphabet so that many are equal, followed by the record's index
+            let mut page = Vec::new();
+
+            for i in 0..n as u32 {
+                let mut record: Vec<_> = (0..key_len).map(|_| rng.next() as u8 % 3).collect();
+                record.extend(i.to_le_bytes().iter().cycle().take(record_len - key_len));
+                page.extend(record);
+            }
+
+            let mut expected: Vec<_> = page.chunks(record_len).collect();
+            expected.sort_by_key(|r| &r[..key_len]);
+            let expected = expected.concat();
+
+            crate::sort_records(&mut page, record_len, key_len);
+            assert!(
+                page == expected,
+                "record_len = {record_len}, key_len = {key_len}, n = {n}"
+            );
+        }
+    }
+}
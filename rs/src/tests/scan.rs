@@ -0,0 +1,72 @@
+use core::cmp::Reverse;
+use std::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use super::Rng;
+
+#[test]
+fn first_unsorted_index() {
+    assert_eq!(crate::first_unsorted_index::<u32>(&[]), None);
+    assert_eq!(crate::first_unsorted_index(&[1]), None);
+
+    // Ascending with repeats, which don't count as out of order
+    for n in [2, 3, 16, 100, 257] {
+        let sorted: Vec<u32> = (0..n).map(|i| 1 + 2 * (i / 2)).collect();
+        assert_eq!(crate::first_unsorted_index(&sorted), None);
+
+        // A single element less than the one before it, anywhere
+        for i in 1..n as usize {
+            let mut v = sorted.clone();
+            v[i] = v[i - 1] - 1;
+
+            let desc: Vec<_> = v.iter().map(|&k| Reverse(k)).collect();
+            assert_eq!(crate::first_unsorted_index(&v), Some(i), "n = {n}");
+            assert_eq!(crate::first_unsorted_index_by(&desc, |x, y| y < x), Some(i));
+            assert_eq!(crate::first_unsorted_index_by_key(&desc, |x| x.0), Some(i));
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn detect_runs() {
+    let mut rng = Rng::new(285);
+
+    for n in [0, 1, 2, 100, 1000] {
+        // Random keys give short runs and few distinct keys longer ones
+        for range in [2, 1000] {
+            let keys = rng.keys(n, range);
+            let ends = crate::detect_runs(&keys);
+            let mut start = 0;
+
+            // Each run is non-descending and ends where the next element is smaller
+            for &end in &ends {
+                assert!(start < end);
+                assert!(keys[start..end].is_sorted());
+                assert!(end == n || keys[end] < keys[end - 1]);
+                start = end;
+            }
+
+            assert_eq!(start, n);
+        }
+    }
+
+    assert_eq!(crate::detect_runs(&[1, 2, 2, 3]), [4]);
+    assert_eq!(crate::detect_runs(&[3, 2, 1]), [1, 2, 3]);
+}
+
+#[test]
+fn is_sorted() {
+    assert!(crate::is_sorted::<u32>(&[]));
+    assert!(crate::is_sorted(&[7]));
+    assert!(crate::is_sorted_by(&[7], |_: &u32, _| panic!("compared")));
+
+    // Equal elements are in order both ways round
+    for n in [2, 3, 16, 100, 257] {
+        let v = std::vec![5u32; n];
+        assert!(crate::is_sorted(&v));
+        assert!(crate::is_sorted_by(&v, |x, y| x < y));
+        assert!(crate::is_sorted_by(&v, |x, y| y < x));
+        assert!(!crate::is_sorted_by(&v, |_, _| true));
+    }
+}
@@ -0,0 +1,25 @@
+use super::Rng;
+
+#[test]
+fn binary_search_matches_std() {
+    let mut rng = Rng::new(295);
+
+    for n in (0..64).chain([100, 1000, 4096, 5000]) {
+        let range = n as u32 / 2 + 1;
+        let mut v = rng.keys(n, range);
+        v.sort();
+
+        for x in 0..=range {
+            // std may return any of several equal elements, but this returns the first
+            let first = v.partition_point(|&y| y < x);
+            let expected = v.binary_search(&x).map(|_| first).map_err(|_| first);
+
+            assert_eq!(crate::binary_search(&v, &x), expected, "n = {n}, x = {x}");
+            assert_eq!(
+                crate::binary_search_by_key(&v, &(x as u64), |&y| y as u64),
+                expected,
+                "n = {n}, x = {x}"
+            );
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::vec::Vec;
+
+use super::Rng;
+use crate::{
+    dust::insert_sort,
+    small_sort::{sort_network, BinaryInsertion, LinearInsertion, Network, SmallSort, MAX_NETWORK},
+};
+
+// Call `f` with every sequence of `n` keys below `n`, which covers every permutation of distinct
+// keys along with every pattern of repeated ones.
+fn for_each_sequence(n: usize, mut f: impl FnMut(&[u32])) {
+    let mut keys = std::vec![0; n];
+
+    loop {
+        f(&keys);
+
+        // Count up in base `n`
+        let Some(i) = keys.iter().rposition(|&k| k + 1 < n as u32) else {
+            return;
+        };
+
+        keys[i] += 1;
+        keys[i + 1..].fill(0);
+    }
+}
+
+fn check_strategy<S: SmallSort>() {
+    for n in 2..=6 {
+        for_each_sequence(n, |keys| {
+            let tagged: Vec<_> = keys.iter().copied().zip(0u32..).collect();
+            let less = &mut |a: &(u32, u32), b: &(u32, u32)| a.0 < b.0;
+
+            let mut expected = tagged.clone();
+            unsafe { insert_sort(expected.as_mut_ptr(), 1, n, less) };
+
+            let mut v = tagged.clone();
+            unsafe { S::sort_small(v.as_mut_ptr(), 0, n, less) };
+            assert_eq!(v, expected, "sort_small on {keys:?}");
+
+            let mut v = tagged.clone();
+            crate::sort_by_with::<S, _, _>(&mut v, |a, b| a.0.cmp(&b.0));
+            assert_eq!(v, expected, "sort_by_with on {keys:?}");
+        });
+    }
+}
+
+#[test]
+fn linear_insertion() {
+    check_strategy::<LinearInsertion>();
+}
+
+#[test]
+fn binary_insertion() {
+    check_strategy::<BinaryInsertion>();
+}
+
+#[test]
+fn network() {
+    check_strategy::<Network>();
+}
+
+#[test]
+fn networks_sort_every_binary_sequence() {
+    // By the 0-1 principle, a network that sorts every sequence of zeros and ones sorts anything
+    for n in 0..=MAX_NETWORK {
+        for bits in 0u32..1 << n {
+            let mut v: Vec<_> = (0..n).map(|i| bits >> i & 1).collect();
+            unsafe { sort_network(v.as_mut_ptr(), n, &mut u32::lt) };
+
+            let ones = bits.count_ones() as usize;
+            assert!(
+                v[..n - ones].iter().all(|&x| x == 0),
+                "n = {n}, bits = {bits:b}"
+            );
+            assert!(
+                v[n - ones..].iter().all(|&x| x == 1),
+                "n = {n}, bits = {bits:b}"
+            );
+        }
+    }
+}
+
+#[test]
+fn sort_unstable_short() {
+    let mut rng = Rng::new(261);
+
+    for n in 0..=2 * MAX_NETWORK {
+        for _ in 0..100 {
+            let mut v = rng.keys(n, 10);
+            let mut expected = v.clone();
+            expected.sort();
+
+            crate::sort_unstable(&mut v);
+            assert_eq!(v, expected);
+        }
+    }
+}
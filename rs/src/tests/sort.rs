@@ -0,0 +1,414 @@
+use core::cmp::Ordering;
+use std::vec::Vec;
+
+use super::{assert_permutation, assert_stable, panic_sweep, Rng, Tracked};
+
+// Sort `keys` tagged with their indices by key alone, and compare against the standard library.
+fn check_against_std(keys: &[u32]) {
+    let mut expected: Vec<_> = keys.iter().copied().zip(0u32..).collect();
+    let mut v = expected.clone();
+
+    expected.sort_by_key(|x| x.0);
+    crate::sort_by_key(&mut v, |x| x.0);
+    assert!(v == expected, "n = {}", keys.len());
+}
+
+#[test]
+fn few_distinct_block_merge() {
+    let mut rng = Rng::new(201);
+
+    // Mid-size inputs with barely enough distinct keys for a block merge, where the last block of
+    // a pass was once left unmerged
+    for n in (600..6000).step_by(31).chain([628, 1500]) {
+        for d in 12..=24 {
+            check_against_std(&rng.keys(n, d));
+        }
+    }
+}
+
+#[test]
+fn few_inversions() {
+    let mut rng = Rng::new(204);
+    let n = 10_000;
+
+    // A trend plus noise, which bounded insertion sort finishes
+    let trend: Vec<_> = (0..n).map(|i| 4 * i + rng.next() as u32 % 16).collect();
+    let mut cmps = 0;
+    let mut v = trend.clone();
+
+    crate::sort_by(&mut v, |a, b| {
+        cmps += 1;
+        a.cmp(b)
+    });
+
+    assert!(cmps < n as usize * 3 / 2, "{cmps} comparisons");
+    check_against_std(&trend);
+
+    // Samples in order, but too many inversions between them for the insertion sort budget
+    let blocks: Vec<_> = (0..n).map(|i| i - i % 64 + (63 - i % 64)).collect();
+    check_against_std(&blocks);
+
+    for keys in [trend, blocks] {
+        panic_sweep::<0>(&keys[..2000], 300, |v, less| {
+            crate::sort_with_options(v, crate::SortOptions::DEFAULT, less);
+        });
+    }
+}
+
+#[test]
+fn try_sort_by_error() {
+    let mut rng = Rng::new(264);
+    let keys = rng.keys(2000, 500);
+
+    let mut v = Tracked::<0>::from_keys(&keys);
+    let mut cmps = 0;
+
+    let res: Result<(), ()> = crate::try_sort_by(&mut v, |a, b| {
+        cmps += 1;
+        Ok(a.cmp(b))
+    });
+
+    assert_eq!(res, Ok(()));
+    assert_stable(&v);
+    assert_permutation(v, keys.len());
+
+    // Fail at points across the whole sort, which must stop calling the comparator after that
+    for k in (1..=cmps).step_by(cmps / 200) {
+        let mut v = Tracked::<0>::from_keys(&keys);
+        let mut calls = 0;
+
+        let res = crate::try_sort_by(&mut v, |a, b| {
+            calls += 1;
+            if calls == k {
+                return Err(k);
+            }
+            Ok(a.cmp(b))
+        });
+
+        assert_eq!(res, Err(k));
+        assert_eq!(calls, k);
+        assert_permutation(v, keys.len());
+    }
+}
+
+#[test]
+fn empty_and_single() {
+    type Sort = fn(&mut [Tracked<0>]) -> bool;
+
+    fn never(_: &Tracked<0>, _: &Tracked<0>) -> bool {
+        panic!("compared")
+    }
+
+    let sorts: [(&str, Sort); 7] = [
+        ("sort", crate::sort),
+        ("sort_in_place", crate::sort_in_place),
+        ("sort_lenient", crate::sort_lenient),
+        ("sort_unstable", crate::sort_unstable),
+        ("sort_with_options", |v| {
+            crate::sort_with_options(v, crate::SortOptions::DEFAULT, never)
+        }),
+        ("sort_with_scratch", |v| {
+            crate::sort_with_scratch(v, &mut [], never)
+        }),
+        ("try_sort_by", |v| {
+            crate::try_sort_by(v, |_, _| Err::<Ordering, _>(())).is_ok()
+        }),
+    ];
+
+    for n in [0, 1] {
+        for (name, sort) in sorts {
+            let mut v = Tracked::from_keys(&[7; 1][..n]);
+            assert!(sort(&mut v), "{name} on {n} elements");
+            assert_permutation(v, n);
+        }
+    }
+}
+
+#[test]
+fn dyn_display() {
+    use core::fmt::Display;
+    use std::string::{String, ToString};
+
+    let mut rng = Rng::new(309);
+    let n = 3000;
+
+    // Values of several types, so that each fat pointer carries one of several vtables
+    let (ints, floats): (Vec<_>, Vec<_>) = rng
+        .keys(n, 1000)
+        .into_iter()
+        .map(|k| (k, k as f64 / 8.0))
+        .unzip();
+    let strings: Vec<_> = ints.iter().map(|k| std::format!("s{k}")).collect();
+
+    let mut v: Vec<&dyn Display> = (0..n)
+        .map(|i| match i % 3 {
+            0 => &ints[i] as &dyn Display,
+            1 => &floats[i],
+            _ => &strings[i],
+        })
+        .collect();
+    let mut expected = v.clone();
+
+    // Long enough to merge through scratch memory, or through collected keys without alloc
+    crate::sort_by_key(&mut v, |x| x.to_string());
+    expected.sort_by_key(|x| x.to_string());
+
+    assert!(v.iter().zip(&expected).all(|(a, b)| core::ptr::eq(*a, *b)));
+    let shown: Vec<String> = v.iter().map(|x| x.to_string()).collect();
+    assert!(shown.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn count_inversions() {
+    let mut rng = Rng::new(340);
+
+    // Lengths past the 32-element runs, so that merge passes count most inversions
+    for n in (0..40).chain((40..400).step_by(17)) {
+        for range in [3, 1000] {
+            let keys = rng.keys(n, range);
+            let mut v = keys.clone();
+
+            let brute = (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+                .filter(|&(i, j)| keys[j] < keys[i])
+                .count();
+
+            assert_eq!(
+                crate::sort_count_inversions(&mut v),
+                brute as u64,
+                "{keys:?}"
+            );
+            assert!(crate::is_sorted(&v));
+        }
+    }
+}
+
+#[test]
+fn sort_split() {
+    let mut rng = Rng::new(278);
+
+    // Every split point, so that either side may be empty or hold all the elements out of place
+    for n in [0, 1, 2, 10, 100, 300] {
+        for range in [3, 1000] {
+            let keys = rng.keys(n, range);
+
+            for mid in 0..=n {
+                let mut v = Tracked::<0>::from_keys(&keys);
+                let (a, b) = v.split_at_mut(mid);
+                let sorted = crate::sort_split(a, b);
+
+                assert_eq!(sorted, keys.is_sorted(), "n = {n}, mid = {mid}");
+                assert_stable(&v);
+                assert_permutation(v, n);
+            }
+        }
+    }
+
+    // Already sorted across the split
+    let mut v = Tracked::<0>::from_keys(&[1, 2, 2, 3, 3, 4]);
+    let (a, b) = v.split_at_mut(3);
+    assert!(crate::sort_split(a, b));
+    assert_permutation(v, 6);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn sort_group() {
+    let mut rng = Rng::new(311);
+
+    for n in [0, 1, 2, 100, 1000] {
+        for range in [1, 3, 1000] {
+            let keys = rng.keys(n, range);
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let groups = crate::sort_group(&mut v);
+
+            // The ranges cover `0..n` in order, each with one key, greater than the last
+            let mut next = 0;
+
+            for g in &groups {
+                assert_eq!(g.start, next);
+                assert!(g.start < g.end);
+                assert!(v[g.clone()].iter().all(|x| x.key == v[g.start].key));
+                assert!(g.start == 0 || v[g.start - 1].key < v[g.start].key);
+                next = g.end;
+            }
+
+            assert_eq!(next, n);
+            assert_stable(&v);
+            assert_permutation(v, n);
+        }
+    }
+
+    let mut v: [u32; 0] = [];
+    assert!(crate::sort_group(&mut v).is_empty());
+
+    let mut v = [5; 100];
+    let groups = crate::sort_group(&mut v);
+    assert!(groups.len() == 1 && groups[0] == (0..100));
+}
+
+#[test]
+fn sort_by_fueled() {
+    let mut rng = Rng::new(332);
+    let n = 5000;
+    let keys = rng.keys(n, 1000);
+    let less = |a: &Tracked<0>, b: &Tracked<0>| a.key < b.key;
+
+    // Too little fuel to get past the first merge pass
+    let mut v = Tracked::<0>::from_keys(&keys);
+    let mut fuel = 1;
+    assert!(!crate::sort_by_fueled(&mut v, less, &mut fuel));
+    assert_eq!(fuel, 0);
+    assert_permutation(v, n);
+
+    // Enough to finish, with some left over
+    let mut v = Tracked::<0>::from_keys(&keys);
+    let mut fuel = 1 << 40;
+    assert!(crate::sort_by_fueled(&mut v, less, &mut fuel));
+    assert!(0 < fuel && fuel < 1 << 40);
+    assert_stable(&v);
+    assert_permutation(v, n);
+
+    // Sorting again with a small budget each time, which the sorted runs left behind make last
+    let mut v = Tracked::<0>::from_keys(&keys);
+    let mut calls = 0;
+
+    while !crate::sort_by_fueled(&mut v, less, &mut 20_000) {
+        calls += 1;
+        assert!(calls < 100, "no progress");
+    }
+
+    assert_stable(&v);
+    assert_permutation(v, n);
+}
+
+#[test]
+fn sort_until() {
+    let mut rng = Rng::new(325);
+    let n = 5000;
+    let keys = rng.keys(n, 1000);
+    let less = |a: &Tracked<0>, b: &Tracked<0>| a.key < b.key;
+
+    // Never stopping finishes, with `stop` seeing all of `v` between passes
+    let mut v = Tracked::<0>::from_keys(&keys);
+    let mut passes = 0;
+
+    assert!(crate::sort_until(&mut v, less, |v| {
+        assert_eq!(v.len(), n);
+        passes += 1;
+        false
+    }));
+
+    assert!(passes > 0);
+    assert_stable(&v);
+    assert_permutation(v, n);
+
+    // Stopping at each pass gives up right there
+    for stop_at in 1..=passes {
+        let mut v = Tracked::<0>::from_keys(&keys);
+        let mut calls = 0;
+
+        let done = crate::sort_until(&mut v, less, |_| {
+            calls += 1;
+            calls == stop_at
+        });
+
+        assert!(!done);
+        assert_eq!(calls, stop_at);
+        assert_permutation(v, n);
+    }
+}
+
+#[test]
+fn sort_initialized() {
+    use core::mem::MaybeUninit;
+
+    let mut rng = Rng::new(320);
+
+    for (n, tail) in [(0, 0), (0, 10), (1, 5), (100, 0), (100, 400), (2000, 3000)] {
+        // The tail holds the smallest keys, so any element read from it would be sorted in front
+        let mut keys = rng.keys(n, 1000);
+        keys.iter_mut().for_each(|k| *k += 1);
+        keys.resize(n + tail, 0);
+
+        let mut buf: Vec<_> = Tracked::<0>::from_keys(&keys)
+            .into_iter()
+            .map(MaybeUninit::new)
+            .collect();
+
+        let sorted = unsafe { crate::sort_initialized(&mut buf, n) };
+        assert_eq!(sorted, keys[..n].is_sorted());
+
+        let v: Vec<_> = buf.drain(..n).map(|x| unsafe { x.assume_init() }).collect();
+        assert_stable(&v);
+
+        // The tail is where it was, and dropping it here leaves no element dropped twice
+        for (x, id) in buf.iter_mut().zip(n as u32..) {
+            let tracked = unsafe { x.assume_init_ref() };
+            assert_eq!((tracked.key, tracked.id), (0, id));
+            unsafe { x.assume_init_drop() };
+        }
+
+        assert_permutation(v, n);
+    }
+}
+
+#[test]
+fn sort_detect_changes() {
+    let mut rng = Rng::new(313);
+
+    for n in [0, 1, 2, 100, 1000] {
+        let scrambled = rng.keys(n, 1000);
+        let mut sorted = scrambled.clone();
+        sorted.sort();
+
+        // Descending with runs of equal elements, which still move when sorted stably
+        let mut descending = sorted.clone();
+        descending.reverse();
+        let ties: Vec<_> = descending.iter().map(|k| k / 100).collect();
+
+        for keys in [&sorted, &descending, &ties, &scrambled] {
+            let mut v = Tracked::<0>::from_keys(keys);
+            let changed = crate::sort_detect_changes(&mut v);
+
+            assert_eq!(changed, !crate::sort(&mut keys.clone()));
+            assert_eq!(changed, !keys.is_sorted(), "{keys:?}");
+            assert_stable(&v);
+            assert_permutation(v, n);
+        }
+    }
+
+    assert!(crate::sort_detect_changes(&mut [3, 2, 2, 1]));
+    assert!(!crate::sort_detect_changes(&mut [1, 2, 2, 3]));
+}
+
+#[test]
+fn few_appended() {
+    let mut rng = Rng::new(310);
+
+    // A sorted prefix with repeats, then a few elements to insert, each equal to some before it
+    for n in [64, 65, 100, 1000, 5000] {
+        for tail in 1..=4 {
+            let mut keys: Vec<_> = (0..(n - tail) as u32).map(|i| i / 4).collect();
+            let top = keys[keys.len() - 1];
+            keys.extend(rng.keys(tail, top));
+
+            let mut v = Tracked::<0>::from_keys(&keys);
+            let mut cmps = 0;
+
+            crate::sort_by(&mut v, |a, b| {
+                cmps += 1;
+                a.cmp(b)
+            });
+
+            // A scan of the prefix, then a binary search per appended element
+            let bound = n + tail * (n.ilog2() as usize + 2);
+            assert!(
+                cmps <= bound,
+                "{cmps} comparisons for n = {n}, {tail} appended"
+            );
+            assert_stable(&v);
+            assert_permutation(v, n);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering::Relaxed},
+    thread,
+    vec::Vec,
+};
+
+use super::Rng;
+
+#[test]
+fn comparisons_within_bound() {
+    let mut rng = Rng::new(269);
+
+    for n in [100, 1000, 10_000, 100_000] {
+        for range in [10, n as u32 / 10, u32::MAX] {
+            let mut v = rng.keys(n, range);
+            let stats = crate::sort_instrumented(&mut v, u32::cmp);
+            assert!(crate::is_sorted(&v));
+
+            // Random inputs take just over `n * ceil(log2 n)` comparisons
+            let log = (usize::BITS - (n - 1).leading_zeros()) as usize;
+            let max = n * log * 3 / 2;
+            assert!(
+                stats.comparisons <= max,
+                "sorting {n} keys below {range} took {} comparisons, over {max}",
+                stats.comparisons
+            );
+            assert!(stats.moves > 0, "n = {n}: no moves counted");
+        }
+    }
+}
+
+#[test]
+fn exact_moves() {
+    // Inserting the last element shifts the other six and writes it back
+    let mut v = [1, 2, 3, 4, 5, 6, 0];
+    let stats = crate::sort_instrumented(&mut v, u32::cmp);
+    assert_eq!((stats.comparisons, stats.moves), (9, 7));
+
+    // A strictly descending input is reversed with 10 swaps
+    let mut v: Vec<u32> = (0..20).rev().collect();
+    let stats = crate::sort_instrumented(&mut v, u32::cmp);
+    assert_eq!((stats.comparisons, stats.moves), (19, 20));
+
+    // A sorted input is only scanned
+    let stats = crate::sort_instrumented(&mut v, u32::cmp);
+    assert_eq!((stats.comparisons, stats.moves), (19, 0));
+}
+
+#[test]
+fn moves_counted_per_call() {
+    let stop = AtomicBool::new(false);
+
+    thread::scope(|s| {
+        // Sort on another thread all along, which must not add to the counts below
+        s.spawn(|| {
+            let mut rng = Rng::new(269);
+
+            while !stop.load(Relaxed) {
+                crate::sort(&mut rng.keys(1000, 100));
+            }
+        });
+
+        for _ in 0..100 {
+            let mut v: Vec<u32> = (0..20).rev().collect();
+            assert_eq!(crate::sort_instrumented(&mut v, u32::cmp).moves, 20);
+        }
+
+        stop.store(true, Relaxed);
+    });
+
+    // Nor do sorts run by the comparator itself
+    let mut v = [1, 2, 3, 4, 5, 6, 0];
+    let stats = crate::sort_instrumented(&mut v, |a, b| {
+        crate::sort(&mut [3, 1, 2]);
+        a.cmp(b)
+    });
+    assert_eq!(stats.moves, 7);
+}
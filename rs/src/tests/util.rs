@@ -0,0 +1,88 @@
+use std::vec::Vec;
+
+use super::{assert_permutation, Tracked};
+
+// Swap `cnt` elements at `a` with those at `b`, among `n` elements, and check that the region at
+// `a` receives the one at `b` in order and the region at `b` receives the one at `a` in any order.
+fn check_cycle_swap<const PAD: usize>(n: usize, a: usize, b: usize, cnt: usize) {
+    let keys: Vec<_> = (0..n as u32).collect();
+    let mut v = Tracked::<PAD>::from_keys(&keys);
+
+    unsafe {
+        let s = v.as_mut_ptr();
+        crate::util::cycle_swap(s.add(a), s.add(b), cnt);
+    }
+
+    let ids = |r: usize| v[r..r + cnt].iter().map(|x| x.id as usize);
+    assert!(
+        ids(a).eq(b..b + cnt),
+        "({a}, {b}, {cnt}) misplaced the second region"
+    );
+
+    let mut moved: Vec<_> = ids(b).collect();
+    moved.sort_unstable();
+    assert!(
+        moved.into_iter().eq(a..a + cnt),
+        "({a}, {b}, {cnt}) misplaced the first region"
+    );
+
+    assert_permutation(v, n);
+}
+
+#[test]
+fn cycle_swap() {
+    for cnt in 1..40 {
+        for gap in [0, 1, 7] {
+            let n = 2 * cnt + gap;
+
+            // Elements small enough to swap in chunks, and ones cycled one at a time
+            for (a, b) in [(0, cnt + gap), (cnt + gap, 0)] {
+                check_cycle_swap::<0>(n, a, b, cnt);
+                check_cycle_swap::<1>(n, a, b, cnt);
+                check_cycle_swap::<2>(n, a, b, cnt);
+            }
+        }
+    }
+}
+
+#[test]
+fn rotate() {
+    let n = 12;
+    let keys: Vec<_> = (0..n as u32).collect();
+
+    for l in 0..=n {
+        for n1 in 0..=n - l {
+            for n2 in 0..=n - l - n1 {
+                let mut v = Tracked::<0>::from_keys(&keys);
+
+                // An empty region at the end starts one past the last element
+                unsafe { crate::util::rotate(v.as_mut_ptr().add(l), n1, n2) };
+
+                let mut expected: Vec<_> = (0..n).collect();
+                expected[l..l + n1 + n2].rotate_left(n1);
+                assert!(
+                    v.iter().map(|x| x.id as usize).eq(expected),
+                    "rotating {n1} and {n2} elements at {l}"
+                );
+                assert_permutation(v, n);
+            }
+        }
+    }
+}
+
+#[test]
+fn insert_zero() {
+    let keys: Vec<_> = (0..3).collect();
+    let mut v = Tracked::<0>::from_keys(&keys);
+
+    // Each element is only read and written back
+    for i in 0..3 {
+        unsafe {
+            crate::util::insert_left(v.as_mut_ptr().add(i), 0);
+            crate::util::insert_right(v.as_mut_ptr().add(i), 0);
+        }
+    }
+
+    assert!(v.iter().map(|x| x.id).eq(0..3));
+    assert_permutation(v, 3);
+}
@@ -0,0 +1,79 @@
+use core::{
+    cell::Cell,
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+use std::vec::Vec;
+
+use super::Rng;
+
+#[test]
+fn sort_checked() {
+    let mut rng = Rng::new(338);
+
+    for n in [0, 1, 10, 100, 1000, 20_000] {
+        for range in [4, 1000, u32::MAX] {
+            let mut v = rng.keys(n, range);
+            let mut expected = v.clone();
+
+            crate::sort_checked(&mut v);
+            expected.sort();
+            assert!(v == expected);
+        }
+    }
+}
+
+// An element whose comparisons overwrite it with the other element once, on the given call,
+// as a broken merge would by copying an element over another instead of moving it.
+struct Corrupting<'a> {
+    key: Cell<u32>,
+    left: &'a Cell<usize>,
+}
+
+impl PartialEq for Corrupting<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Corrupting<'_> {}
+
+impl PartialOrd for Corrupting<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Corrupting<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.left.set(self.left.get().wrapping_sub(1));
+
+        if self.left.get() == 0 {
+            self.key.set(other.key.get());
+        }
+
+        self.key.cmp(&other.key)
+    }
+}
+
+impl Hash for Corrupting<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.get().hash(state);
+    }
+}
+
+#[test]
+#[should_panic = "elements lost or duplicated"]
+fn duplicated_element() {
+    let keys = Rng::new(338).keys(1000, u32::MAX);
+    let left = Cell::new(5000);
+    let mut v: Vec<_> = keys
+        .into_iter()
+        .map(|key| Corrupting {
+            key: Cell::new(key),
+            left: &left,
+        })
+        .collect();
+
+    crate::sort_checked(&mut v);
+}
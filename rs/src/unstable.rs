@@ -0,0 +1,194 @@
+use core::ptr;
+
+use crate::{
+    dust::{insert_sort, MIN_MERGE_SORT},
+    scan::next_non_desc_run,
+    small_sort::{sort_network, MAX_NETWORK},
+    util::{conditional, Less},
+};
+
+/// Sort `s..s + n` with heapsort.
+pub unsafe fn heapsort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
+    // Restore the max-heap property of the subtree at `node` within the first `n` elements
+    let sift_down = |mut node: usize, n: usize, less: &mut F| loop {
+        let mut child = 2 * node + 1;
+
+        if child >= n {
+            break;
+        }
+
+        child += (child + 1 < n && less(&*s.add(child), &*s.add(child + 1))) as usize;
+
+        if !less(&*s.add(node), &*s.add(child)) {
+            break;
+        }
+
+        ptr::swap_nonoverlapping(s.add(node), s.add(child), 1);
+        node = child;
+    };
+
+    for i in (0..n / 2).rev() {
+        sift_down(i, n, less);
+    }
+
+    for end in (1..n).rev() {
+        ptr::swap_nonoverlapping(s, s.add(end), 1);
+        sift_down(0, end, less);
+    }
+}
+
+// Move the median of the first, middle and last elements of `s..s + n` to the front.
+unsafe fn select_pivot<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
+    let (a, b, c) = (s, s.add(n / 2), s.add(n - 1));
+
+    let ab = less(&*b, &*a);
+    let bc = less(&*c, &*b);
+    let ac = less(&*c, &*a);
+
+    // If `b` is not the median, it's `a` iff `a` lies between `b` and `c`
+    let median = if ab == bc {
+        b
+    } else if ab != ac {
+        a
+    } else {
+        c
+    };
+
+    if median != s {
+        ptr::swap_nonoverlapping(s, median, 1);
+    }
+}
+
+// Partition `s..s + n` around the pivot at `s`, stopping on equal elements from both sides so that
+// runs of equal elements are split evenly.
+//
+// Return the final index of the pivot.
+unsafe fn partition<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) -> usize {
+    let p = s;
+    let mut i = 1;
+    let mut j = n - 1;
+
+    loop {
+        while i <= j && less(&*s.add(i), &*p) {
+            i += 1;
+        }
+
+        while i <= j && less(&*p, &*s.add(j)) {
+            j -= 1;
+        }
+
+        if i >= j {
+            break;
+        }
+
+        ptr::swap_nonoverlapping(s.add(i), s.add(j), 1);
+        i += 1;
+        j -= 1;
+    }
+
+    if j > 0 {
+        ptr::swap_nonoverlapping(s, s.add(j), 1);
+    }
+
+    j
+}
+
+// Sort `s..s + n` for short `n`, with a sorting network where possible.
+unsafe fn sort_small<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) {
+    if n <= MAX_NETWORK {
+        sort_network(s, n, less);
+    } else {
+        insert_sort(s, 1, n, less);
+    }
+}
+
+// Sort `s..s + n` with introsort, switching to heapsort after `depth` more levels of partitioning.
+// Only the smaller side is sorted recursively, so the recursion depth is `O(log n)`.
+unsafe fn quicksort<T, F: Less<T>>(mut s: *mut T, mut n: usize, mut depth: u32, less: &mut F) {
+    while n >= MIN_MERGE_SORT {
+        if depth == 0 {
+            return heapsort(s, n, less);
+        }
+
+        depth -= 1;
+        select_pivot(s, n, less);
+        let mid = partition(s, n, less);
+
+        if mid < n - mid {
+            quicksort(s, mid, depth, less);
+            (s, n) = (s.add(mid + 1), n - mid - 1);
+        } else {
+            quicksort(s.add(mid + 1), n - mid - 1, depth, less);
+            n = mid;
+        }
+    }
+
+    sort_small(s, n, less);
+}
+
+/// Sort `s..s + n` without preserving the order of equal elements.
+///
+/// Return `true` if `s..s + n` was already sorted, in which case it is left untouched.
+pub unsafe fn sort<T, F: Less<T>>(s: *mut T, n: usize, less: &mut F) -> bool {
+    let head = next_non_desc_run(s, n, less);
+
+    if head == n {
+        return true;
+    }
+
+    if n <= MAX_NETWORK {
+        sort_network(s, n, less);
+    } else if n < MIN_MERGE_SORT {
+        insert_sort(s, head, n, less);
+    } else {
+        quicksort(s, n, 2 * n.ilog2(), less);
+    }
+
+    false
+}
+
+/// Reorder `s..s + n` so that the element at index `k` is the one that would be there if sorted,
+/// with no greater elements before it and no smaller elements after it.
+pub unsafe fn select<T, F: Less<T>>(mut s: *mut T, mut n: usize, mut k: usize, less: &mut F) {
+    if next_non_desc_run(s, n, less) == n {
+        return;
+    }
+
+    let mut depth = 2 * n.ilog2();
+
+    while n >= MIN_MERGE_SORT {
+        // Extremes only need a linear scan
+        if k == 0 || k == n - 1 {
+            let mut m = 0;
+
+            for i in 1..n {
+                let (x, y) = if k == 0 { (i, m) } else { (m, i) };
+                m = conditional(m, i, less(&*s.add(x), &*s.add(y)));
+            }
+
+            if m != k {
+                ptr::swap_nonoverlapping(s.add(m), s.add(k), 1);
+            }
+
+            return;
+        }
+
+        if depth == 0 {
+            return heapsort(s, n, less);
+        }
+
+        depth -= 1;
+        select_pivot(s, n, less);
+        let mid = partition(s, n, less);
+
+        if k < mid {
+            n = mid;
+        } else if k > mid {
+            (s, n, k) = (s.add(mid + 1), n - mid - 1, k - mid - 1);
+        } else {
+            return;
+        }
+    }
+
+    sort_small(s, n, less);
+}
@@ -4,9 +4,48 @@ use core::ptr;
 pub trait Less<T>: FnMut(&T, &T) -> bool {}
 impl<T, F: FnMut(&T, &T) -> bool> Less<T> for F {}
 
-/// Represents a hole created when moving an element into stack space
+/// A gap left by moving an element out of memory, which is filled again when dropped.
+///
+/// Read an element into stack space that won't drop it, such as a
+/// [`ManuallyDrop`](core::mem::ManuallyDrop), and create a hole at its old position pointing to
+/// that copy. Then move other elements through the gap by writing to [`pos`](Hole::pos) and
+/// updating it. When the hole is dropped, even while unwinding from a panic, the copy is written
+/// into the gap, so the region always ends up a permutation of its original elements.
+///
+/// ```
+/// use core::mem::ManuallyDrop;
+/// use dustsort::raw::Hole;
+///
+/// // Shift the last element left past the greater elements before it
+/// fn insert_last(v: &mut [i32], mut less: impl FnMut(&i32, &i32) -> bool) {
+///     let s = v.as_mut_ptr();
+///
+///     unsafe {
+///         let tmp = ManuallyDrop::new(s.add(v.len() - 1).read());
+///         let mut hole = Hole::new(s.add(v.len() - 1), &*tmp);
+///
+///         while hole.pos > s && less(&tmp, &*hole.pos.sub(1)) {
+///             hole.pos.write(hole.pos.sub(1).read());
+///             hole.pos = hole.pos.sub(1);
+///         }
+///     }
+/// }
+///
+/// let mut v = [1, 3, 5, 2];
+/// insert_last(&mut v, |a, b| a < b);
+/// assert_eq!(v, [1, 2, 3, 5]);
+///
+/// // A panic midway still fills the gap, so no element is lost or duplicated
+/// let mut v = [1, 3, 5, 2];
+/// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+///     insert_last(&mut v, |a, b| if *b == 3 { panic!() } else { a < b });
+/// }));
+///
+/// assert!(result.is_err());
+/// assert_eq!(v, [1, 3, 2, 5]);
+/// ```
 pub struct Hole<T> {
-    /// Pointer to the position of the hole in memory
+    /// Pointer to the current position of the gap.
     pub pos: *mut T,
 
     // Pointer to the value that should be dropped back into the hole
@@ -14,7 +53,12 @@ pub struct Hole<T> {
 }
 
 impl<T> Hole<T> {
-    /// Create a new hole at position `pos` with value pointed to by `src`.
+    /// Create a new hole at position `pos`, to be filled with the value pointed to by `src`.
+    ///
+    /// # Safety
+    ///
+    /// Until the hole is dropped, `src` must be valid for reads and `pos` must always be valid for
+    /// writes. The value at `src` must not be used or dropped after the hole is dropped.
     pub const unsafe fn new(pos: *mut T, src: *const T) -> Self {
         Self { pos, src }
     }
@@ -23,11 +67,16 @@ impl<T> Hole<T> {
     ///
     /// Write the value at `dst` into this hole and replace it with the value at `src`, moving the
     /// hole to `src`.
+    ///
+    /// # Safety
+    ///
+    /// `src` and `dst` must be valid for reads and writes.
     #[inline(always)]
     pub unsafe fn cycle(&mut self, src: *mut T, dst: *mut T) {
         self.pos.write(dst.read());
         dst.write(src.read());
         self.pos = src;
+        count_moves(2);
     }
 }
 
@@ -57,15 +106,32 @@ pub unsafe fn cycle_swap<T>(a: *mut T, b: *mut T, cnt: usize) {
     // Hint that regions don't overlap
     core::hint::assert_unchecked(a.add(cnt) <= b || b.add(cnt) <= a);
 
-    let tmp = a.read();
-    a.write(b.read());
+    // Small elements are faster to swap in vectorized chunks than to cycle one at a time
+    if core::mem::size_of::<T>() <= 16 {
+        ptr::swap_nonoverlapping(a, b, cnt);
+    } else {
+        let tmp = a.read();
+        a.write(b.read());
 
-    for i in 1..cnt {
-        b.add(i - 1).write(a.add(i).read());
-        a.add(i).write(b.add(i).read());
+        for i in 1..cnt {
+            b.add(i - 1).write(a.add(i).read());
+            a.add(i).write(b.add(i).read());
+        }
+
+        b.add(cnt - 1).write(tmp);
     }
 
-    b.add(cnt - 1).write(tmp);
+    count_moves(2 * cnt);
+}
+
+/// Record `cnt` element moves if the `stats` feature is enabled.
+#[inline(always)]
+pub fn count_moves(cnt: usize) {
+    #[cfg(feature = "stats")]
+    crate::stats::count_moves(cnt);
+
+    #[cfg(not(feature = "stats"))]
+    let _ = cnt;
 }
 
 /// Return `b` if `is_b` or `a` otherwise.
@@ -74,18 +140,22 @@ pub fn conditional<T: Copy>(a: T, b: T, is_b: bool) -> T {
     [a, b][is_b as usize]
 }
 
-/// Shift the element at `s` to the left by `cnt` elements.
+/// Shift the element at `s` to the left by `cnt` elements. With `cnt == 0`, the element is only
+/// read and written back, so `s` must still point to a valid element.
 pub unsafe fn insert_left<T>(s: *mut T, cnt: usize) {
     let tmp = s.read();
     ptr::copy(s.sub(cnt), s.add(1).sub(cnt), cnt);
     s.sub(cnt).write(tmp);
+    count_moves(cnt + 1);
 }
 
-/// Shift the element at `s` to the right by `cnt` elements.
+/// Shift the element at `s` to the right by `cnt` elements. With `cnt == 0`, the element is only
+/// read and written back, so `s` must still point to a valid element.
 pub unsafe fn insert_right<T>(s: *mut T, cnt: usize) {
     let tmp = s.read();
     ptr::copy(s.add(1), s, cnt);
     s.add(cnt).write(tmp);
+    count_moves(cnt + 1);
 }
 
 /// Reverse the region `l..r`  in-place.
@@ -95,6 +165,7 @@ pub unsafe fn reverse<T>(mut l: *mut T, mut r: *mut T) {
         r = r.sub(1);
         ptr::swap(l, r);
         l = l.add(1);
+        count_moves(2);
     }
 }
 
@@ -102,12 +173,20 @@ pub unsafe fn reverse<T>(mut l: *mut T, mut r: *mut T) {
 pub unsafe fn rotate<T>(mut s: *mut T, mut n1: usize, mut n2: usize) {
     // `slice::rotate` uses 24 elements of stack space -- not approved
 
+    // Either region may be empty, such as an empty buffer in `Buffer::insert`, and then `s` may be
+    // the end of the array
+    if n1 == 0 || n2 == 0 {
+        return;
+    }
+
     while n1 > 1 && n2 > 1 {
         if n1 > n2 {
             ptr::swap_nonoverlapping(s.add(n1 - n2), s.add(n1), n2);
+            count_moves(2 * n2);
             n1 -= n2;
         } else {
             ptr::swap_nonoverlapping(s, s.add(n1), n1);
+            count_moves(2 * n1);
             n2 -= n1;
             s = s.add(n1);
         }
@@ -122,18 +201,44 @@ pub unsafe fn rotate<T>(mut s: *mut T, mut n1: usize, mut n2: usize) {
 
 /// Return the value `i` in `0..=n` such that for all `j` in `0..i`, `f(j)` and for all `j` in
 /// `i..n`, `!f(j)`. The caller guarantees `f` is partitioned in such a manner.
-fn lower_bound(mut n: usize, mut f: impl FnMut(usize) -> bool) -> usize {
+pub fn lower_bound(mut n: usize, mut f: impl FnMut(usize) -> bool) -> usize {
     let mut i = 0;
 
     while n > 0 {
         let h = n / 2;
-        i += conditional(0, n - h, f(i + h));
+        i += core::hint::select_unpredictable(f(i + h), n - h, 0);
         n = h;
     }
 
     i
 }
 
+/// Like [`lower_bound`], but probe exponentially upwards from `0` first, taking `O(log i)` steps
+/// to find `i`.
+pub fn gallop_lower_bound(n: usize, mut f: impl FnMut(usize) -> bool) -> usize {
+    let mut d = 1;
+
+    while d <= n && f(d - 1) {
+        d *= 2;
+    }
+
+    let l = d / 2;
+    l + lower_bound(usize::min(n, d - 1) - l, |x| f(l + x))
+}
+
+/// Like [`lower_bound`], but probe exponentially downwards from `n` first, taking `O(log (n - i))`
+/// steps to find `i`.
+pub fn gallop_lower_bound_rev(n: usize, mut f: impl FnMut(usize) -> bool) -> usize {
+    let mut d = 1;
+
+    while d <= n && !f(n - d) {
+        d *= 2;
+    }
+
+    let l = (n + 1).saturating_sub(d);
+    l + lower_bound(n - d / 2 - l, |x| f(l + x))
+}
+
 /// Return the number of elements in the region `s..s + n` which are `less` than `val`.
 pub unsafe fn search_left<T, F: Less<T>>(
     s: *const T,
@@ -0,0 +1,45 @@
+use core::hash::{Hash, Hasher};
+
+use crate::sort;
+
+// FNV-1a, which is enough to tell elements apart without `std`'s hashers
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
+
+// Return a checksum of the multiset of elements in `v`, which doesn't depend on their order
+fn checksum<T: Hash>(v: &[T]) -> u64 {
+    v.iter().fold(0, |sum, x| {
+        let mut h = Fnv(0xcbf2_9ce4_8422_2325);
+        x.hash(&mut h);
+        sum.wrapping_add(h.finish())
+    })
+}
+
+/// Sort `v` like [`sort`], and in debug builds check that the sort neither lost nor duplicated any
+/// element, by comparing a checksum of the element hashes before and after.
+///
+/// Debug builds panic with "elements lost or duplicated" if the checksums differ. Release builds
+/// only sort.
+///
+/// Return `true` if `v` was already sorted, in which case it is left untouched.
+pub fn sort_checked<T: Ord + Hash>(v: &mut [T]) -> bool {
+    let before = cfg!(debug_assertions).then(|| checksum(v));
+    let was_sorted = sort(v);
+
+    if let Some(before) = before {
+        assert!(checksum(v) == before, "elements lost or duplicated");
+    }
+
+    was_sorted
+}